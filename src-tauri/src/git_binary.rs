@@ -0,0 +1,357 @@
+use base64::Engine;
+use git2::{ObjectType, Oid};
+use serde::Serialize;
+
+/// Default cap for inlining an image version as a base64 thumbnail.
+pub const DEFAULT_THUMBNAIL_MAX_BYTES: u64 = 256 * 1024;
+
+#[derive(Clone, Copy, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImageDimensions {
+    pub width: u32,
+    pub height: u32,
+}
+
+/// What the diff panel can show for a binary entry instead of its contents.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GitBinaryInfo {
+    pub old_size: Option<u64>,
+    pub new_size: Option<u64>,
+    pub old_id: Option<String>,
+    pub new_id: Option<String>,
+    pub mime_type: String,
+    pub old_image: Option<ImageDimensions>,
+    pub new_image: Option<ImageDimensions>,
+    /// `data:` URLs of each image version, only when under the size cap.
+    pub old_thumbnail: Option<String>,
+    pub new_thumbnail: Option<String>,
+}
+
+fn read_u16_be(bytes: &[u8], offset: usize) -> Option<u32> {
+    let slice = bytes.get(offset..offset + 2)?;
+    Some(u16::from_be_bytes([slice[0], slice[1]]) as u32)
+}
+
+fn read_u16_le(bytes: &[u8], offset: usize) -> Option<u32> {
+    let slice = bytes.get(offset..offset + 2)?;
+    Some(u16::from_le_bytes([slice[0], slice[1]]) as u32)
+}
+
+fn read_u24_le(bytes: &[u8], offset: usize) -> Option<u32> {
+    let slice = bytes.get(offset..offset + 3)?;
+    Some(slice[0] as u32 | (slice[1] as u32) << 8 | (slice[2] as u32) << 16)
+}
+
+fn read_u32_be(bytes: &[u8], offset: usize) -> Option<u32> {
+    let slice = bytes.get(offset..offset + 4)?;
+    Some(u32::from_be_bytes([slice[0], slice[1], slice[2], slice[3]]))
+}
+
+fn jpeg_dimensions(bytes: &[u8]) -> Option<ImageDimensions> {
+    let mut offset = 2;
+    while offset + 4 <= bytes.len() {
+        if bytes[offset] != 0xFF {
+            return None;
+        }
+        let marker = bytes[offset + 1];
+        if marker == 0xFF {
+            offset += 1;
+            continue;
+        }
+        // Start-of-frame markers carry the image size; C4, C8 and CC are
+        // table/extension markers that share the range.
+        if (0xC0..=0xCF).contains(&marker) && ![0xC4, 0xC8, 0xCC].contains(&marker) {
+            return Some(ImageDimensions {
+                height: read_u16_be(bytes, offset + 5)?,
+                width: read_u16_be(bytes, offset + 7)?,
+            });
+        }
+        let length = read_u16_be(bytes, offset + 2)? as usize;
+        offset += 2 + length;
+    }
+    None
+}
+
+fn webp_dimensions(bytes: &[u8]) -> Option<ImageDimensions> {
+    match bytes.get(12..16)? {
+        b"VP8 " => Some(ImageDimensions {
+            width: read_u16_le(bytes, 26)? & 0x3FFF,
+            height: read_u16_le(bytes, 28)? & 0x3FFF,
+        }),
+        b"VP8L" => {
+            let bits = bytes.get(21..25)?;
+            Some(ImageDimensions {
+                width: 1 + (((bits[1] as u32 & 0x3F) << 8) | bits[0] as u32),
+                height: 1
+                    + (((bits[3] as u32 & 0x0F) << 10)
+                        | ((bits[2] as u32) << 2)
+                        | ((bits[1] as u32 & 0xC0) >> 6)),
+            })
+        }
+        b"VP8X" => Some(ImageDimensions {
+            width: 1 + read_u24_le(bytes, 24)?,
+            height: 1 + read_u24_le(bytes, 27)?,
+        }),
+        _ => None,
+    }
+}
+
+/// Reads the pixel size from PNG, JPEG, GIF and WebP headers.
+pub fn image_dimensions(bytes: &[u8]) -> Option<ImageDimensions> {
+    if bytes.starts_with(b"\x89PNG\r\n\x1a\n") {
+        return Some(ImageDimensions {
+            width: read_u32_be(bytes, 16)?,
+            height: read_u32_be(bytes, 20)?,
+        });
+    }
+    if bytes.starts_with(b"GIF87a") || bytes.starts_with(b"GIF89a") {
+        return Some(ImageDimensions {
+            width: read_u16_le(bytes, 6)?,
+            height: read_u16_le(bytes, 8)?,
+        });
+    }
+    if bytes.starts_with(&[0xFF, 0xD8]) {
+        return jpeg_dimensions(bytes);
+    }
+    if bytes.starts_with(b"RIFF") && bytes.get(8..12) == Some(b"WEBP") {
+        return webp_dimensions(bytes);
+    }
+    None
+}
+
+fn sniff_mime_type(bytes: &[u8]) -> Option<&'static str> {
+    let signatures: [(&[u8], &str); 12] = [
+        (b"\x89PNG\r\n\x1a\n", "image/png"),
+        (&[0xFF, 0xD8, 0xFF], "image/jpeg"),
+        (b"GIF87a", "image/gif"),
+        (b"GIF89a", "image/gif"),
+        (b"%PDF-", "application/pdf"),
+        (b"PK\x03\x04", "application/zip"),
+        (&[0x1F, 0x8B], "application/gzip"),
+        (b"\0asm", "application/wasm"),
+        (b"\x7fELF", "application/x-elf"),
+        (b"SQLite format 3\0", "application/vnd.sqlite3"),
+        (b"wOFF", "font/woff"),
+        (b"wOF2", "font/woff2"),
+    ];
+    if bytes.starts_with(b"RIFF") && bytes.get(8..12) == Some(b"WEBP") {
+        return Some("image/webp");
+    }
+    if bytes.starts_with(&[0x00, 0x00, 0x01, 0x00]) {
+        return Some("image/x-icon");
+    }
+    signatures
+        .iter()
+        .find(|(signature, _)| bytes.starts_with(signature))
+        .map(|(_, mime)| *mime)
+}
+
+fn mime_type_from_extension(path: &str) -> Option<&'static str> {
+    let extension = path.rsplit_once('.')?.1.to_ascii_lowercase();
+    let mime = match extension.as_str() {
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "ico" => "image/x-icon",
+        "icns" => "image/icns",
+        "bmp" => "image/bmp",
+        "pdf" => "application/pdf",
+        "zip" => "application/zip",
+        "gz" => "application/gzip",
+        "wasm" => "application/wasm",
+        "ttf" => "font/ttf",
+        "otf" => "font/otf",
+        "woff" => "font/woff",
+        "woff2" => "font/woff2",
+        "mp3" => "audio/mpeg",
+        "wav" => "audio/wav",
+        "mp4" => "video/mp4",
+        "db" | "sqlite" => "application/vnd.sqlite3",
+        _ => return None,
+    };
+    Some(mime)
+}
+
+/// Detects a MIME type from magic bytes, falling back to the file extension.
+pub fn detect_mime_type(path: &str, bytes: Option<&[u8]>) -> String {
+    bytes
+        .and_then(sniff_mime_type)
+        .or_else(|| mime_type_from_extension(path))
+        .unwrap_or("application/octet-stream")
+        .to_string()
+}
+
+fn thumbnail(path: &str, bytes: &[u8], max_bytes: Option<u64>) -> Option<String> {
+    let max_bytes = max_bytes?;
+    let mime_type = detect_mime_type(path, Some(bytes));
+    if !mime_type.starts_with("image/") || bytes.len() as u64 > max_bytes {
+        return None;
+    }
+    Some(format!(
+        "data:{};base64,{}",
+        mime_type,
+        base64::engine::general_purpose::STANDARD.encode(bytes)
+    ))
+}
+
+fn blob_id(id: Oid, bytes: Option<&[u8]>) -> Option<String> {
+    if !id.is_zero() {
+        return Some(id.to_string());
+    }
+    // Working-tree files are not hashed by the diff, so hash them here.
+    let bytes = bytes?;
+    Oid::hash_object(ObjectType::Blob, bytes)
+        .ok()
+        .map(|oid| oid.to_string())
+}
+
+/// Describes both versions of a binary entry. `thumbnail_max_bytes` of `None`
+/// skips thumbnails entirely.
+pub fn binary_info(
+    path: &str,
+    old: (Oid, Option<&[u8]>),
+    new: (Oid, Option<&[u8]>),
+    thumbnail_max_bytes: Option<u64>,
+) -> GitBinaryInfo {
+    let (old_oid, old_bytes) = old;
+    let (new_oid, new_bytes) = new;
+    let mime_type = detect_mime_type(path, new_bytes.or(old_bytes));
+    GitBinaryInfo {
+        old_size: old_bytes.map(|bytes| bytes.len() as u64),
+        new_size: new_bytes.map(|bytes| bytes.len() as u64),
+        old_id: old_bytes.and_then(|bytes| blob_id(old_oid, Some(bytes))),
+        new_id: new_bytes.and_then(|bytes| blob_id(new_oid, Some(bytes))),
+        old_image: old_bytes.and_then(image_dimensions),
+        new_image: new_bytes.and_then(image_dimensions),
+        old_thumbnail: old_bytes.and_then(|bytes| thumbnail(path, bytes, thumbnail_max_bytes)),
+        new_thumbnail: new_bytes.and_then(|bytes| thumbnail(path, bytes, thumbnail_max_bytes)),
+        mime_type,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn size(bytes: &[u8]) -> Option<(u32, u32)> {
+        image_dimensions(bytes).map(|image| (image.width, image.height))
+    }
+
+    /// Every proper prefix of a header must be rejected, never read past.
+    fn assert_prefixes_rejected(bytes: &[u8]) {
+        for len in 0..bytes.len() {
+            assert_eq!(size(&bytes[..len]), None, "prefix of {} bytes", len);
+        }
+    }
+
+    fn png(width: u32, height: u32) -> Vec<u8> {
+        let mut bytes = b"\x89PNG\r\n\x1a\n\0\0\0\x0dIHDR".to_vec();
+        bytes.extend_from_slice(&width.to_be_bytes());
+        bytes.extend_from_slice(&height.to_be_bytes());
+        bytes
+    }
+
+    fn gif(width: u16, height: u16) -> Vec<u8> {
+        let mut bytes = b"GIF89a".to_vec();
+        bytes.extend_from_slice(&width.to_le_bytes());
+        bytes.extend_from_slice(&height.to_le_bytes());
+        bytes
+    }
+
+    fn jpeg(width: u16, height: u16) -> Vec<u8> {
+        let mut bytes = vec![0xFF, 0xD8];
+        // APP0 and a Huffman table (C4) come before the frame.
+        bytes.extend_from_slice(&[0xFF, 0xE0, 0x00, 0x04, 0x4A, 0x46]);
+        bytes.extend_from_slice(&[0xFF, 0xC4, 0x00, 0x03, 0x00]);
+        // Fill bytes before a marker are allowed.
+        bytes.extend_from_slice(&[0xFF, 0xFF, 0xC2, 0x00, 0x0B, 0x08]);
+        bytes.extend_from_slice(&height.to_be_bytes());
+        bytes.extend_from_slice(&width.to_be_bytes());
+        bytes
+    }
+
+    fn webp(chunk: &[u8; 4], payload: &[u8]) -> Vec<u8> {
+        let mut bytes = b"RIFF\0\0\0\0WEBP".to_vec();
+        bytes.extend_from_slice(chunk);
+        bytes.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(payload);
+        bytes
+    }
+
+    #[test]
+    fn reads_png_and_gif_dimensions() {
+        assert_eq!(size(&png(640, 480)), Some((640, 480)));
+        assert_eq!(size(&png(u32::MAX, 1)), Some((u32::MAX, 1)));
+        assert_eq!(size(&gif(300, 200)), Some((300, 200)));
+        let mut gif87 = gif(1, 2);
+        gif87[4] = b'7';
+        assert_eq!(size(&gif87), Some((1, 2)));
+    }
+
+    #[test]
+    fn reads_jpeg_dimensions_past_other_segments() {
+        assert_eq!(size(&jpeg(1920, 1080)), Some((1920, 1080)));
+    }
+
+    #[test]
+    fn rejects_malformed_jpeg_segments() {
+        // A segment that does not start with a marker.
+        assert_eq!(size(&[0xFF, 0xD8, 0x00, 0xC0, 0x00, 0x11]), None);
+        // A segment length running past the end of the data.
+        assert_eq!(size(&[0xFF, 0xD8, 0xFF, 0xE1, 0xFF, 0xFF, 0x00]), None);
+        // Only fill bytes.
+        assert_eq!(size(&[0xFF; 16]), None);
+    }
+
+    #[test]
+    fn reads_webp_dimensions() {
+        let mut lossy = vec![0x30, 0x01, 0x00, 0x9D, 0x01, 0x2A];
+        lossy.extend_from_slice(&400u16.to_le_bytes());
+        lossy.extend_from_slice(&(300u16 | 0xC000).to_le_bytes());
+        assert_eq!(size(&webp(b"VP8 ", &lossy)), Some((400, 300)));
+
+        let bits: u32 = (100 - 1) | ((50 - 1) << 14);
+        let mut lossless = vec![0x2F];
+        lossless.extend_from_slice(&bits.to_le_bytes());
+        assert_eq!(size(&webp(b"VP8L", &lossless)), Some((100, 50)));
+
+        let mut extended = vec![0x10, 0, 0, 0];
+        extended.extend_from_slice(&(70_000u32 - 1).to_le_bytes()[..3]);
+        extended.extend_from_slice(&(2u32 - 1).to_le_bytes()[..3]);
+        assert_eq!(size(&webp(b"VP8X", &extended)), Some((70_000, 2)));
+
+        assert_eq!(size(&webp(b"ALPH", &[0; 16])), None);
+    }
+
+    #[test]
+    fn rejects_truncated_headers() {
+        assert_prefixes_rejected(&png(640, 480));
+        assert_prefixes_rejected(&gif(300, 200));
+        assert_prefixes_rejected(&jpeg(1920, 1080));
+        let mut lossy = vec![0x30, 0x01, 0x00, 0x9D, 0x01, 0x2A];
+        lossy.extend_from_slice(&[0x90, 0x01, 0x2C, 0x01]);
+        assert_prefixes_rejected(&webp(b"VP8 ", &lossy));
+        assert_prefixes_rejected(&webp(b"VP8L", &[0x2F, 0x63, 0xC0, 0x0C, 0x00]));
+        assert_prefixes_rejected(&webp(b"VP8X", &[0; 10]));
+    }
+
+    #[test]
+    fn ignores_unknown_formats() {
+        assert_eq!(size(b""), None);
+        assert_eq!(size(b"BM\x36\0\0\0\0\0\0\0\x36\0\0\0"), None);
+        assert_eq!(size(b"RIFF\0\0\0\0WAVEfmt "), None);
+    }
+
+    #[test]
+    fn detects_mime_type_from_bytes_before_extension() {
+        assert_eq!(detect_mime_type("logo.bin", Some(&png(1, 1))), "image/png");
+        assert_eq!(detect_mime_type("logo.png", Some(b"plain")), "image/png");
+        assert_eq!(
+            detect_mime_type("photo.webp", Some(&webp(b"VP8X", &[0; 10]))),
+            "image/webp"
+        );
+        assert_eq!(detect_mime_type("data", None), "application/octet-stream");
+    }
+}
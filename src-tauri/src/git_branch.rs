@@ -0,0 +1,396 @@
+use crate::{resolve_base_commit, run_git_command_output};
+use git2::build::CheckoutBuilder;
+use git2::{Branch, BranchType, ErrorCode, Repository};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+
+#[derive(Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GitBranchKind {
+    Local,
+    Remote,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GitBranchCommit {
+    pub id: String,
+    pub short_id: String,
+    pub summary: String,
+    pub author_name: String,
+    /// Commit time in milliseconds since the Unix epoch.
+    pub time: i64,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GitBranchInfo {
+    /// Short name, e.g. `feature/x` or `origin/feature/x`.
+    pub name: String,
+    pub kind: GitBranchKind,
+    /// Checked out in the repository `path` points at.
+    pub is_head: bool,
+    /// The worktree that has this branch checked out, if any.
+    pub worktree_path: Option<String>,
+    pub upstream: Option<String>,
+    /// Commits ahead of and behind the upstream; `None` without one.
+    pub ahead: Option<usize>,
+    pub behind: Option<usize>,
+    pub last_commit: Option<GitBranchCommit>,
+}
+
+fn local_ref(name: &str) -> String {
+    format!("refs/heads/{}", name)
+}
+
+fn validate_name(name: &str) -> Result<String, String> {
+    let trimmed = name.trim();
+    if trimmed.is_empty() || !Branch::name_is_valid(trimmed).map_err(|error| error.to_string())? {
+        return Err(format!("'{}' is not a valid branch name", name));
+    }
+    Ok(trimmed.to_string())
+}
+
+fn find_local<'repo>(repo: &'repo Repository, name: &str) -> Result<Branch<'repo>, String> {
+    repo.find_branch(name.trim(), BranchType::Local)
+        .map_err(|error| match error.code() {
+            ErrorCode::NotFound => format!("branch '{}' not found", name),
+            _ => error.to_string(),
+        })
+}
+
+/// Maps `refs/heads/...` to the worktree that has it checked out, the main
+/// checkout included.
+fn checked_out_branches(repo: &Repository) -> HashMap<String, String> {
+    let root = repo
+        .workdir()
+        .unwrap_or_else(|| repo.path())
+        .to_string_lossy()
+        .to_string();
+    let mut branches = HashMap::new();
+    let Ok(output) = run_git_command_output(&root, &["worktree", "list", "--porcelain"]) else {
+        return branches;
+    };
+    let mut worktree = None;
+    for line in output.lines() {
+        if let Some(path) = line.strip_prefix("worktree ") {
+            worktree = Some(path.to_string());
+        } else if let Some(reference) = line.strip_prefix("branch ") {
+            if let Some(path) = &worktree {
+                branches.insert(reference.to_string(), path.clone());
+            }
+        }
+    }
+    branches
+}
+
+fn branch_info(
+    repo: &Repository,
+    branch: &Branch,
+    kind: GitBranchKind,
+    checked_out: &HashMap<String, String>,
+) -> Result<GitBranchInfo, String> {
+    let name = branch
+        .name()
+        .map_err(|error| error.to_string())?
+        .unwrap_or_default()
+        .to_string();
+    let reference = branch.get();
+    let target = reference.target();
+
+    let (upstream, ahead, behind) = match branch.upstream() {
+        Ok(upstream) => {
+            let upstream_name = upstream
+                .name()
+                .map_err(|error| error.to_string())?
+                .map(str::to_string);
+            let counts = match (target, upstream.get().target()) {
+                (Some(local), Some(remote)) => repo.graph_ahead_behind(local, remote).ok(),
+                _ => None,
+            };
+            (
+                upstream_name,
+                counts.map(|(ahead, _)| ahead),
+                counts.map(|(_, behind)| behind),
+            )
+        }
+        Err(_) => (None, None, None),
+    };
+
+    let last_commit = reference.peel_to_commit().ok().map(|commit| {
+        let id = commit.id().to_string();
+        GitBranchCommit {
+            short_id: id.chars().take(7).collect(),
+            id,
+            summary: commit.summary().unwrap_or_default().to_string(),
+            author_name: commit.author().name().unwrap_or_default().to_string(),
+            time: commit.time().seconds() * 1000,
+        }
+    });
+
+    Ok(GitBranchInfo {
+        is_head: branch.is_head(),
+        worktree_path: reference
+            .name()
+            .and_then(|full_name| checked_out.get(full_name).cloned()),
+        name,
+        kind,
+        upstream,
+        ahead,
+        behind,
+        last_commit,
+    })
+}
+
+/// Lists local and remote-tracking branches, or only one kind.
+#[tauri::command]
+pub fn list_git_branches(
+    path: String,
+    kind: Option<GitBranchKind>,
+) -> Result<Vec<GitBranchInfo>, String> {
+    let repo = Repository::discover(&path).map_err(|error| error.to_string())?;
+    let checked_out = checked_out_branches(&repo);
+    let branch_type = kind.map(|kind| match kind {
+        GitBranchKind::Local => BranchType::Local,
+        GitBranchKind::Remote => BranchType::Remote,
+    });
+
+    let mut branches = Vec::new();
+    for entry in repo
+        .branches(branch_type)
+        .map_err(|error| error.to_string())?
+    {
+        let (branch, branch_type) = entry.map_err(|error| error.to_string())?;
+        let kind = match branch_type {
+            BranchType::Local => GitBranchKind::Local,
+            BranchType::Remote => GitBranchKind::Remote,
+        };
+        // `origin/HEAD` is a pointer to another remote branch, not a branch.
+        if kind == GitBranchKind::Remote && branch.get().symbolic_target().is_some() {
+            continue;
+        }
+        branches.push(branch_info(&repo, &branch, kind, &checked_out)?);
+    }
+    branches.sort_by(|a, b| {
+        (a.kind == GitBranchKind::Remote, &a.name).cmp(&(b.kind == GitBranchKind::Remote, &b.name))
+    });
+    Ok(branches)
+}
+
+/// Creates `name` at `start_point` (HEAD when omitted). A remote branch as
+/// the start point becomes the new branch's upstream.
+#[tauri::command]
+pub fn create_git_branch(
+    path: String,
+    name: String,
+    start_point: Option<String>,
+    checkout: Option<bool>,
+) -> Result<GitBranchInfo, String> {
+    let repo = Repository::discover(&path).map_err(|error| error.to_string())?;
+    let name = validate_name(&name)?;
+    if repo.find_branch(&name, BranchType::Local).is_ok() {
+        return Err(format!("branch '{}' already exists", name));
+    }
+
+    let start_point = start_point
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty());
+    let (commit, upstream) = match &start_point {
+        Some(start) => {
+            let upstream = repo
+                .find_branch(start, BranchType::Remote)
+                .ok()
+                .and_then(|branch| branch.name().ok().flatten().map(str::to_string));
+            (resolve_base_commit(&repo, start)?, upstream)
+        }
+        None => (
+            repo.head()
+                .and_then(|head| head.peel_to_commit())
+                .map_err(|error| error.to_string())?,
+            None,
+        ),
+    };
+
+    let mut branch = repo
+        .branch(&name, &commit, false)
+        .map_err(|error| error.to_string())?;
+    if let Some(upstream) = &upstream {
+        branch
+            .set_upstream(Some(upstream))
+            .map_err(|error| error.to_string())?;
+    }
+    if checkout.unwrap_or(false) {
+        checkout_branch(&repo, &name)?;
+    }
+
+    let branch = find_local(&repo, &name)?;
+    branch_info(
+        &repo,
+        &branch,
+        GitBranchKind::Local,
+        &checked_out_branches(&repo),
+    )
+}
+
+#[tauri::command]
+pub fn rename_git_branch(
+    path: String,
+    name: String,
+    new_name: String,
+    force: Option<bool>,
+) -> Result<GitBranchInfo, String> {
+    let repo = Repository::discover(&path).map_err(|error| error.to_string())?;
+    let new_name = validate_name(&new_name)?;
+    let mut branch = find_local(&repo, &name)?;
+    let force = force.unwrap_or(false);
+    if !force && repo.find_branch(&new_name, BranchType::Local).is_ok() {
+        return Err(format!("branch '{}' already exists", new_name));
+    }
+    let renamed = branch
+        .rename(&new_name, force)
+        .map_err(|error| error.to_string())?;
+    branch_info(
+        &repo,
+        &renamed,
+        GitBranchKind::Local,
+        &checked_out_branches(&repo),
+    )
+}
+
+/// Switches the working tree to `name`, carrying local edits along when they
+/// do not collide with the switch, like `git switch`. Colliding edits are
+/// never overwritten.
+fn checkout_branch(repo: &Repository, name: &str) -> Result<(), String> {
+    let reference = local_ref(name);
+    if let Some(worktree) = checked_out_branches(repo).get(&reference) {
+        // `git worktree list` may report a path through a symlink (macOS
+        // `/tmp`, say), so both sides are resolved before comparing.
+        let here = repo.workdir().and_then(|path| path.canonicalize().ok());
+        let there = Path::new(worktree).canonicalize().ok();
+        if here.is_none() || here != there {
+            return Err(format!(
+                "branch '{}' is already checked out at '{}'",
+                name, worktree
+            ));
+        }
+    }
+
+    let target = repo
+        .revparse_single(&reference)
+        .map_err(|error| error.to_string())?;
+    let mut checkout = CheckoutBuilder::new();
+    checkout.safe();
+    repo.checkout_tree(&target, Some(&mut checkout))
+        .map_err(|error| match error.code() {
+            ErrorCode::Conflict => format!(
+                "local changes would be overwritten by checking out '{}'; commit or stash them first",
+                name
+            ),
+            _ => error.to_string(),
+        })?;
+    repo.set_head(&reference).map_err(|error| error.to_string())
+}
+
+/// Checks out a local branch. A name that only exists as a remote branch
+/// (`feature/x` for `origin/feature/x`) gets a local tracking branch first.
+#[tauri::command]
+pub fn checkout_git_branch(path: String, name: String) -> Result<GitBranchInfo, String> {
+    let repo = Repository::discover(&path).map_err(|error| error.to_string())?;
+    let name = name.trim().to_string();
+
+    if repo.find_branch(&name, BranchType::Local).is_err() {
+        let mut remotes = Vec::new();
+        for entry in repo
+            .branches(Some(BranchType::Remote))
+            .map_err(|error| error.to_string())?
+        {
+            let (branch, _) = entry.map_err(|error| error.to_string())?;
+            let Some(remote_name) = branch.name().ok().flatten().map(str::to_string) else {
+                continue;
+            };
+            if remote_name
+                .split_once('/')
+                .is_some_and(|(_, short)| short == name)
+            {
+                remotes.push((remote_name, branch));
+            }
+        }
+        match remotes.len() {
+            0 => return Err(format!("branch '{}' not found", name)),
+            1 => {
+                let (remote_name, branch) = remotes.remove(0);
+                let commit = branch
+                    .get()
+                    .peel_to_commit()
+                    .map_err(|error| error.to_string())?;
+                let mut local = repo
+                    .branch(&name, &commit, false)
+                    .map_err(|error| error.to_string())?;
+                local
+                    .set_upstream(Some(&remote_name))
+                    .map_err(|error| error.to_string())?;
+            }
+            _ => {
+                return Err(format!(
+                    "'{}' matches several remote branches; create a local branch explicitly",
+                    name
+                ))
+            }
+        }
+    }
+
+    checkout_branch(&repo, &name)?;
+    let branch = find_local(&repo, &name)?;
+    branch_info(
+        &repo,
+        &branch,
+        GitBranchKind::Local,
+        &checked_out_branches(&repo),
+    )
+}
+
+/// Deletes a local branch. Without `force`, refuses a branch that is not
+/// merged into its upstream (or into HEAD when it has none), like
+/// `git branch -d`. A checked-out branch is never deleted.
+#[tauri::command]
+pub fn delete_git_branch(path: String, name: String, force: Option<bool>) -> Result<(), String> {
+    let repo = Repository::discover(&path).map_err(|error| error.to_string())?;
+    let mut branch = find_local(&repo, &name)?;
+    let reference = branch.get().name().unwrap_or_default().to_string();
+    if let Some(worktree) = checked_out_branches(&repo).get(&reference) {
+        return Err(format!(
+            "branch '{}' is checked out at '{}'",
+            name.trim(),
+            worktree
+        ));
+    }
+
+    if !force.unwrap_or(false) {
+        let tip = branch
+            .get()
+            .target()
+            .ok_or_else(|| format!("branch '{}' has no target", name))?;
+        let merged_into = match branch.upstream() {
+            Ok(upstream) => upstream.get().target(),
+            Err(_) => repo.head().ok().and_then(|head| head.target()),
+        };
+        let merged = match merged_into {
+            Some(target) => {
+                target == tip
+                    || repo
+                        .graph_descendant_of(target, tip)
+                        .map_err(|error| error.to_string())?
+            }
+            None => false,
+        };
+        if !merged {
+            return Err(format!(
+                "branch '{}' is not fully merged; delete it with force to discard its commits",
+                name.trim()
+            ));
+        }
+    }
+
+    branch.delete().map_err(|error| error.to_string())
+}
@@ -0,0 +1,180 @@
+use git2::{Commit, Oid, Repository, RepositoryState, Signature};
+use serde::Deserialize;
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GitIdentity {
+    pub name: String,
+    pub email: String,
+}
+
+#[derive(Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GitCommitOptions {
+    /// Replaces HEAD instead of adding a new commit on top of it.
+    pub amend: Option<bool>,
+    pub author: Option<GitIdentity>,
+    pub committer: Option<GitIdentity>,
+    /// Appends a `Signed-off-by` trailer for the committer.
+    pub sign_off: Option<bool>,
+    /// Runner that produced the change (`codex`, `claude`, ...), recorded as a
+    /// `Runner` trailer.
+    pub runner: Option<String>,
+}
+
+fn signature(
+    repo: &Repository,
+    identity: Option<&GitIdentity>,
+) -> Result<Signature<'static>, String> {
+    match identity {
+        Some(identity) => Signature::now(&identity.name, &identity.email),
+        None => repo.signature(),
+    }
+    .map_err(|error| error.to_string())
+}
+
+/// Commits a pending merge adds as extra parents, read from `MERGE_HEAD`.
+fn merge_head_ids(repo: &mut Repository) -> Result<Vec<Oid>, String> {
+    if repo.state() != RepositoryState::Merge {
+        return Ok(Vec::new());
+    }
+    let mut ids = Vec::new();
+    repo.mergehead_foreach(|id| {
+        ids.push(*id);
+        true
+    })
+    .map_err(|error| error.to_string())?;
+    Ok(ids)
+}
+
+fn is_trailer_line(line: &str) -> bool {
+    match line.split_once(": ") {
+        Some((key, _)) => {
+            !key.is_empty()
+                && key
+                    .chars()
+                    .all(|ch| ch.is_ascii_alphanumeric() || ch == '-')
+        }
+        None => false,
+    }
+}
+
+/// Appends trailers git-style: in the message's existing trailer block when it
+/// ends with one, otherwise in a new paragraph. Trailers already present are
+/// not repeated.
+fn append_trailers(message: &str, trailers: &[String]) -> String {
+    let mut result = message.trim_end().to_string();
+    let existing: Vec<&str> = result.lines().collect();
+    let trailers: Vec<&String> = trailers
+        .iter()
+        .filter(|trailer| !existing.contains(&trailer.as_str()))
+        .collect();
+    if trailers.is_empty() {
+        result.push('\n');
+        return result;
+    }
+
+    let last_paragraph = result.rsplit("\n\n").next().unwrap_or_default();
+    let has_trailer_block = result.contains("\n\n") && last_paragraph.lines().all(is_trailer_line);
+    result.push_str(if has_trailer_block { "\n" } else { "\n\n" });
+    for trailer in trailers {
+        result.push_str(trailer);
+        result.push('\n');
+    }
+    result
+}
+
+#[tauri::command]
+pub fn git_commit(
+    repo_path: String,
+    message: String,
+    options: Option<GitCommitOptions>,
+) -> Result<String, String> {
+    let options = options.unwrap_or_default();
+    if message.trim().is_empty() {
+        return Err("commit message is empty".to_string());
+    }
+    let mut repo = Repository::discover(&repo_path).map_err(|error| error.to_string())?;
+    let amend = options.amend.unwrap_or(false);
+    let merge_head_ids = merge_head_ids(&mut repo)?;
+
+    let head = match repo.head() {
+        Ok(head) => Some(head.peel_to_commit().map_err(|error| error.to_string())?),
+        Err(_) => None,
+    };
+    if amend && head.is_none() {
+        return Err("cannot amend: the branch has no commits yet".to_string());
+    }
+    let merge_heads = merge_head_ids
+        .into_iter()
+        .map(|id| repo.find_commit(id).map_err(|error| error.to_string()))
+        .collect::<Result<Vec<_>, _>>()?;
+    if amend && !merge_heads.is_empty() {
+        return Err("cannot amend: a merge is in progress".to_string());
+    }
+
+    let mut index = repo.index().map_err(|error| error.to_string())?;
+    if index.has_conflicts() {
+        return Err("cannot commit: resolve merge conflicts first".to_string());
+    }
+    let tree_id = index.write_tree().map_err(|error| error.to_string())?;
+    let tree = repo.find_tree(tree_id).map_err(|error| error.to_string())?;
+
+    // An amend may only reword the commit, and a merge may keep HEAD's tree,
+    // so both are allowed without changes.
+    let head_tree_id = head.as_ref().map(|commit| commit.tree_id());
+    let nothing_staged = match head_tree_id {
+        Some(head_tree_id) => head_tree_id == tree_id,
+        None => tree.is_empty(),
+    };
+    if nothing_staged && !amend && merge_heads.is_empty() {
+        return Err("nothing to commit: no changes are staged".to_string());
+    }
+
+    let committer = signature(&repo, options.committer.as_ref())?;
+    let author = match (&options.author, &head) {
+        (Some(identity), _) => signature(&repo, Some(identity))?,
+        // Amending keeps the original author unless told otherwise.
+        (None, Some(commit)) if amend => commit.author().to_owned(),
+        (None, _) => signature(&repo, None)?,
+    };
+
+    let mut trailers = Vec::new();
+    if let Some(runner) = options
+        .runner
+        .as_deref()
+        .map(str::trim)
+        .filter(|runner| !runner.is_empty())
+    {
+        trailers.push(format!("Runner: {}", runner));
+    }
+    if options.sign_off.unwrap_or(false) {
+        trailers.push(format!(
+            "Signed-off-by: {} <{}>",
+            committer.name().unwrap_or_default(),
+            committer.email().unwrap_or_default()
+        ));
+    }
+    let message = append_trailers(&message, &trailers);
+
+    let commit_id = match (&head, amend) {
+        (Some(commit), true) => commit.amend(
+            Some("HEAD"),
+            Some(&author),
+            Some(&committer),
+            None,
+            Some(&message),
+            Some(&tree),
+        ),
+        _ => {
+            let parents: Vec<&Commit> = head.iter().chain(&merge_heads).collect();
+            repo.commit(Some("HEAD"), &author, &committer, &message, &tree, &parents)
+        }
+    }
+    .map_err(|error| error.to_string())?;
+    if !merge_heads.is_empty() {
+        repo.cleanup_state().map_err(|error| error.to_string())?;
+    }
+
+    Ok(commit_id.to_string())
+}
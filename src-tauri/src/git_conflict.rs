@@ -0,0 +1,424 @@
+use crate::git_diff::is_binary_content;
+use crate::git_index::{open_repo, relative_path};
+use crate::git_merge::collect_conflicts;
+use git2::{Oid, Repository};
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GitConflictHunk {
+    /// 1-based lines of the `<<<<<<<` and `>>>>>>>` markers in `markedContent`.
+    pub start_line: usize,
+    pub end_line: usize,
+    pub ours: String,
+    pub ancestor: Option<String>,
+    pub theirs: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GitConflictFile {
+    pub path: String,
+    pub kind: String,
+    pub ancestor_content: Option<String>,
+    pub our_content: Option<String>,
+    pub their_content: Option<String>,
+    /// What the file currently holds in the working tree, which may already
+    /// be partly resolved.
+    pub workdir_content: Option<String>,
+    /// A fresh three-way merge of the three versions with diff3-style markers.
+    pub marked_content: Option<String>,
+    pub hunks: Vec<GitConflictHunk>,
+    pub is_binary: bool,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GitConflictsResponse {
+    pub root: String,
+    pub files: Vec<GitConflictFile>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GitConflictResolution {
+    Ours,
+    Theirs,
+    /// Write the `content` passed with the command.
+    Content,
+}
+
+fn read_blob(repo: &Repository, id: Option<&String>) -> Result<Option<Vec<u8>>, String> {
+    let Some(id) = id else {
+        return Ok(None);
+    };
+    let oid = Oid::from_str(id).map_err(|error| error.to_string())?;
+    let blob = repo.find_blob(oid).map_err(|error| error.to_string())?;
+    Ok(Some(blob.content().to_vec()))
+}
+
+/// Runs `git merge-file --diff3` over the three versions. A missing side
+/// merges as an empty file.
+fn merge_with_markers(
+    ancestor: Option<&[u8]>,
+    ours: Option<&[u8]>,
+    theirs: Option<&[u8]>,
+) -> Result<String, String> {
+    let temp_dir = private_temp_dir()?;
+    let paths: Vec<_> = ["ours", "base", "theirs"]
+        .iter()
+        .map(|label| temp_dir.join(label))
+        .collect();
+    let contents = [ours, ancestor, theirs];
+
+    let result = (|| {
+        for (path, content) in paths.iter().zip(contents) {
+            let mut file = std::fs::OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(path)
+                .map_err(|error| error.to_string())?;
+            file.write_all(content.unwrap_or_default())
+                .map_err(|error| error.to_string())?;
+        }
+        // Exit codes 0..127 are the number of conflicts; only higher codes
+        // (or a signal) mean merge-file itself failed.
+        let output = Command::new("git")
+            .args(["merge-file", "-p", "--diff3"])
+            .args(["-L", "ours", "-L", "base", "-L", "theirs"])
+            .args(&paths)
+            .output()
+            .map_err(|error| error.to_string())?;
+        match output.status.code() {
+            Some(code) if (0..128).contains(&code) => {
+                Ok(String::from_utf8_lossy(&output.stdout).to_string())
+            }
+            _ => Err(String::from_utf8_lossy(&output.stderr).trim().to_string()),
+        }
+    })();
+    let _ = std::fs::remove_dir_all(&temp_dir);
+    result
+}
+
+/// Creates a fresh directory only this process can use for merge inputs.
+/// `create_dir` fails on anything already at the path, symlinks included,
+/// so a name another process planted or a concurrent merge picked is never
+/// reused.
+fn private_temp_dir() -> Result<PathBuf, String> {
+    static COUNTER: AtomicUsize = AtomicUsize::new(0);
+    let mut builder = std::fs::DirBuilder::new();
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::DirBuilderExt;
+        builder.mode(0o700);
+    }
+    loop {
+        let stamp = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|duration| duration.as_nanos())
+            .unwrap_or_default();
+        let dir = std::env::temp_dir().join(format!(
+            "prompter-merge-{}-{}-{}",
+            std::process::id(),
+            COUNTER.fetch_add(1, Ordering::Relaxed),
+            stamp
+        ));
+        match builder.create(&dir) {
+            Ok(()) => return Ok(dir),
+            Err(error) if error.kind() == std::io::ErrorKind::AlreadyExists => continue,
+            Err(error) => return Err(error.to_string()),
+        }
+    }
+}
+
+fn parse_conflict_hunks(marked: &str) -> Vec<GitConflictHunk> {
+    enum Section {
+        Outside,
+        Ours,
+        Ancestor,
+        Theirs,
+    }
+    let mut hunks = Vec::new();
+    let mut section = Section::Outside;
+    let mut start_line = 0;
+    let (mut ours, mut ancestor, mut theirs) = (String::new(), None::<String>, String::new());
+
+    for (index, line) in marked.split_inclusive('\n').enumerate() {
+        let marker = |prefix: &str| {
+            line.starts_with(prefix)
+                && matches!(
+                    line[prefix.len()..].chars().next(),
+                    None | Some(' ' | '\n' | '\r')
+                )
+        };
+        match section {
+            Section::Outside if marker("<<<<<<<") => {
+                section = Section::Ours;
+                start_line = index + 1;
+                ours.clear();
+                ancestor = None;
+                theirs.clear();
+            }
+            Section::Outside => {}
+            Section::Ours if marker("|||||||") => {
+                section = Section::Ancestor;
+                ancestor = Some(String::new());
+            }
+            Section::Ours | Section::Ancestor if marker("=======") => section = Section::Theirs,
+            Section::Ours => ours.push_str(line),
+            Section::Ancestor => ancestor.get_or_insert_with(String::new).push_str(line),
+            Section::Theirs if marker(">>>>>>>") => {
+                hunks.push(GitConflictHunk {
+                    start_line,
+                    end_line: index + 1,
+                    ours: std::mem::take(&mut ours),
+                    ancestor: ancestor.take(),
+                    theirs: std::mem::take(&mut theirs),
+                });
+                section = Section::Outside;
+            }
+            Section::Theirs => theirs.push_str(line),
+        }
+    }
+    hunks
+}
+
+fn text(bytes: &Option<Vec<u8>>) -> Option<String> {
+    bytes
+        .as_ref()
+        .map(|bytes| String::from_utf8_lossy(bytes).to_string())
+}
+
+#[tauri::command]
+pub fn get_git_conflicts(path: String) -> Result<GitConflictsResponse, String> {
+    let repo = open_repo(&path)?;
+    let root = repo
+        .workdir()
+        .unwrap_or_else(|| Path::new(""))
+        .to_path_buf();
+    let index = repo.index().map_err(|error| error.to_string())?;
+
+    let mut files = Vec::new();
+    for conflict in collect_conflicts(&index)? {
+        let ancestor = read_blob(&repo, conflict.ancestor_id.as_ref())?;
+        let ours = read_blob(&repo, conflict.our_id.as_ref())?;
+        let theirs = read_blob(&repo, conflict.their_id.as_ref())?;
+        let workdir = std::fs::read(root.join(&conflict.path)).ok();
+
+        let is_binary = [&ancestor, &ours, &theirs, &workdir]
+            .iter()
+            .any(|content| content.as_deref().map(is_binary_content) == Some(true));
+        let (marked_content, hunks) = if is_binary {
+            (None, Vec::new())
+        } else {
+            let marked =
+                merge_with_markers(ancestor.as_deref(), ours.as_deref(), theirs.as_deref())?;
+            let hunks = parse_conflict_hunks(&marked);
+            (Some(marked), hunks)
+        };
+
+        files.push(GitConflictFile {
+            path: conflict.path,
+            kind: conflict.kind,
+            ancestor_content: if is_binary { None } else { text(&ancestor) },
+            our_content: if is_binary { None } else { text(&ours) },
+            their_content: if is_binary { None } else { text(&theirs) },
+            workdir_content: if is_binary { None } else { text(&workdir) },
+            marked_content,
+            hunks,
+            is_binary,
+        });
+    }
+
+    Ok(GitConflictsResponse {
+        root: root.to_string_lossy().to_string(),
+        files,
+    })
+}
+
+/// Stages the working-tree state of `rel_path`, which clears its conflict
+/// entries. A deleted file is resolved as a deletion.
+fn mark_resolved(repo: &Repository, root: &Path, rel_path: &Path) -> Result<(), String> {
+    let mut index = repo.index().map_err(|error| error.to_string())?;
+    if root.join(rel_path).exists() {
+        index
+            .add_path(rel_path)
+            .map_err(|error| error.to_string())?;
+    } else {
+        index
+            .remove_path(rel_path)
+            .map_err(|error| error.to_string())?;
+    }
+    index.write().map_err(|error| error.to_string())
+}
+
+#[tauri::command]
+pub fn resolve_git_conflict(
+    path: String,
+    file_path: String,
+    resolution: GitConflictResolution,
+    content: Option<String>,
+) -> Result<(), String> {
+    let repo = open_repo(&path)?;
+    let root = repo
+        .workdir()
+        .unwrap_or_else(|| Path::new(""))
+        .to_path_buf();
+    let rel_path = relative_path(&root, &file_path)?;
+    let target = root.join(&rel_path);
+
+    let resolved = match resolution {
+        GitConflictResolution::Content => Some(
+            content
+                .ok_or_else(|| "content is required to resolve with explicit content".to_string())?
+                .into_bytes(),
+        ),
+        side => {
+            let index = repo.index().map_err(|error| error.to_string())?;
+            let wanted = rel_path.to_string_lossy().replace('\\', "/");
+            let conflict = collect_conflicts(&index)?
+                .into_iter()
+                .find(|conflict| conflict.path == wanted)
+                .ok_or_else(|| format!("'{}' is not conflicted", file_path))?;
+            let id = if side == GitConflictResolution::Ours {
+                conflict.our_id
+            } else {
+                conflict.their_id
+            };
+            // The chosen side may have deleted the file.
+            read_blob(&repo, id.as_ref())?
+        }
+    };
+
+    match resolved {
+        Some(bytes) => {
+            if let Some(parent) = target.parent() {
+                std::fs::create_dir_all(parent).map_err(|error| error.to_string())?;
+            }
+            std::fs::write(&target, bytes).map_err(|error| error.to_string())?;
+        }
+        None => {
+            if target.exists() {
+                std::fs::remove_file(&target).map_err(|error| error.to_string())?;
+            }
+        }
+    }
+    mark_resolved(&repo, &root, &rel_path)
+}
+
+#[tauri::command]
+pub fn mark_git_conflict_resolved(path: String, file_path: String) -> Result<(), String> {
+    let repo = open_repo(&path)?;
+    let root = repo
+        .workdir()
+        .unwrap_or_else(|| Path::new(""))
+        .to_path_buf();
+    let rel_path = relative_path(&root, &file_path)?;
+    mark_resolved(&repo, &root, &rel_path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    type Hunk<'a> = (usize, usize, &'a str, Option<&'a str>, &'a str);
+
+    fn hunks(marked: &str) -> Vec<(usize, usize, String, Option<String>, String)> {
+        parse_conflict_hunks(marked)
+            .into_iter()
+            .map(|hunk| {
+                (
+                    hunk.start_line,
+                    hunk.end_line,
+                    hunk.ours,
+                    hunk.ancestor,
+                    hunk.theirs,
+                )
+            })
+            .collect()
+    }
+
+    fn expected(hunks: &[Hunk]) -> Vec<(usize, usize, String, Option<String>, String)> {
+        hunks
+            .iter()
+            .map(|&(start, end, ours, ancestor, theirs)| {
+                (
+                    start,
+                    end,
+                    ours.to_string(),
+                    ancestor.map(str::to_string),
+                    theirs.to_string(),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn parses_two_way_hunks() {
+        let marked = "a\n<<<<<<< ours\nb\n=======\nB\nB2\n>>>>>>> theirs\nc\n<<<<<<< ours\n=======\nd\n>>>>>>> theirs\n";
+        assert_eq!(
+            hunks(marked),
+            expected(&[(2, 7, "b\n", None, "B\nB2\n"), (9, 12, "", None, "d\n")])
+        );
+    }
+
+    #[test]
+    fn parses_diff3_ancestors() {
+        let marked = "<<<<<<< ours\nx\n||||||| base\no\n=======\ny\n>>>>>>> theirs\n";
+        assert_eq!(
+            hunks(marked),
+            expected(&[(1, 7, "x\n", Some("o\n"), "y\n")])
+        );
+        // An empty ancestor is still an ancestor.
+        let marked = "<<<<<<<\nx\n|||||||\n=======\ny\n>>>>>>>\n";
+        assert_eq!(hunks(marked), expected(&[(1, 6, "x\n", Some(""), "y\n")]));
+    }
+
+    #[test]
+    fn keeps_marker_like_text_inside_hunks() {
+        let marked = concat!(
+            "<<<<<<< ours\n",
+            "<<<<<<<< not a marker\n",
+            "<<<<<<< nested opener\n",
+            ">>>>>>> stray closer\n",
+            "=========\n",
+            "=======\n",
+            "=======x\n",
+            "||||||| late base\n",
+            ">>>>>>>>\n",
+            ">>>>>>> theirs\n",
+        );
+        assert_eq!(
+            hunks(marked),
+            expected(&[(
+                1,
+                10,
+                "<<<<<<<< not a marker\n<<<<<<< nested opener\n>>>>>>> stray closer\n=========\n",
+                None,
+                "=======x\n||||||| late base\n>>>>>>>>\n"
+            )])
+        );
+        // Markers outside a hunk start nothing.
+        assert!(hunks("=======\n>>>>>>> x\n|||||||\n").is_empty());
+    }
+
+    #[test]
+    fn handles_missing_trailing_newline_and_crlf() {
+        let marked = "<<<<<<< ours\nx\n=======\ny\n>>>>>>> theirs";
+        assert_eq!(hunks(marked), expected(&[(1, 5, "x\n", None, "y\n")]));
+        let marked = "<<<<<<< ours\nx\n=======\ny>>>>>>> theirs";
+        assert!(hunks(marked).is_empty());
+        let marked = "<<<<<<< ours\r\nx\r\n=======\r\ny\r\n>>>>>>> theirs\r\n";
+        assert_eq!(hunks(marked), expected(&[(1, 5, "x\r\n", None, "y\r\n")]));
+    }
+
+    #[test]
+    fn drops_an_unterminated_hunk() {
+        let marked = "<<<<<<< ours\nx\n=======\ny\n>>>>>>> theirs\n<<<<<<< ours\nz\n=======\n";
+        assert_eq!(hunks(marked), expected(&[(1, 5, "x\n", None, "y\n")]));
+    }
+}
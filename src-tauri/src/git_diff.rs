@@ -0,0 +1,899 @@
+use crate::git_binary::{self, GitBinaryInfo};
+use crate::git_intraline::{self, GitDiffSpan, GitIntralineGranularity};
+use crate::git_submodule::{self, GitSubmoduleChange};
+use git2::{
+    Commit, Delta, Diff, DiffDelta, DiffFile, DiffFindOptions, DiffOptions, Oid, Patch, Pathspec,
+    PathspecFlags, Repository, Status, StatusOptions, Tree,
+};
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, path::Path};
+
+const DEFAULT_CONTEXT_LINES: u32 = 3;
+const DEFAULT_SIMILARITY_THRESHOLD: u16 = 50;
+
+#[derive(Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GitDiffMode {
+    /// Whole old/new file contents, diffed by the consumer.
+    #[default]
+    Full,
+    /// libgit2 hunks with typed, numbered lines.
+    Hunks,
+}
+
+#[derive(Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GitDiffScope {
+    /// Committed tree against the working tree, staged or not.
+    #[default]
+    All,
+    /// Committed tree against the index.
+    Staged,
+    /// Index against the working tree.
+    Unstaged,
+}
+
+#[derive(Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GitWhitespaceMode {
+    #[default]
+    None,
+    /// Ignore whitespace at line ends (`--ignore-space-at-eol`).
+    Eol,
+    /// Ignore changes in the amount of whitespace (`-b`).
+    Change,
+    /// Ignore all whitespace (`-w`).
+    All,
+}
+
+#[derive(Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GitDiffRequestOptions {
+    pub mode: Option<GitDiffMode>,
+    pub scope: Option<GitDiffScope>,
+    pub context_lines: Option<u32>,
+    /// Pair deletes with adds (including untracked files) as renames.
+    pub detect_renames: Option<bool>,
+    pub detect_copies: Option<bool>,
+    /// Similarity percentage (0-100) a pair needs to count as a rename/copy.
+    pub similarity_threshold: Option<u16>,
+    /// Inline image versions of binary entries as base64 `data:` URLs.
+    pub include_thumbnails: Option<bool>,
+    pub thumbnail_max_bytes: Option<u64>,
+    /// Compare against `merge-base(base, HEAD)` instead of the base branch's
+    /// tip (`base...HEAD`), so later commits on the base do not show up.
+    pub three_dot: Option<bool>,
+    /// Mark the changed parts of modified lines (hunks mode only).
+    pub intraline: Option<GitIntralineGranularity>,
+    pub whitespace: Option<GitWhitespaceMode>,
+    /// Limit the diff to paths matching these pathspecs.
+    pub include_paths: Option<Vec<String>>,
+    /// Leave out paths matching these pathspecs, e.g. `package-lock.json`.
+    pub exclude_paths: Option<Vec<String>>,
+    /// Files larger than this (either side, in bytes) are listed without
+    /// their contents or hunks.
+    pub max_file_size: Option<u64>,
+    /// Defaults to true.
+    pub include_untracked: Option<bool>,
+    pub include_ignored: Option<bool>,
+    /// Nest each changed submodule's own working-tree changes under its
+    /// entry.
+    pub recurse_submodules: Option<bool>,
+}
+
+impl GitDiffRequestOptions {
+    pub fn mode(&self) -> GitDiffMode {
+        self.mode.unwrap_or_default()
+    }
+
+    fn thumbnail_max_bytes(&self) -> Option<u64> {
+        if !self.include_thumbnails.unwrap_or(false) {
+            return None;
+        }
+        Some(
+            self.thumbnail_max_bytes
+                .unwrap_or(git_binary::DEFAULT_THUMBNAIL_MAX_BYTES),
+        )
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GitDiffLine {
+    /// `context`, `add` or `delete`.
+    pub kind: &'static str,
+    pub old_line_no: Option<u32>,
+    pub new_line_no: Option<u32>,
+    pub content: String,
+    /// Changed ranges of `content` when the line is one side of a modified
+    /// line pair and intraline highlighting was requested.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub changes: Option<Vec<GitDiffSpan>>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GitDiffHunk {
+    pub header: String,
+    pub old_start: u32,
+    pub old_lines: u32,
+    pub new_start: u32,
+    pub new_lines: u32,
+    pub lines: Vec<GitDiffLine>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GitDiffFile {
+    pub path: String,
+    /// Source path of a rename or copy.
+    pub old_path: Option<String>,
+    pub status: String,
+    pub old_content: String,
+    pub new_content: String,
+    pub is_binary: bool,
+    /// Change between HEAD and the index, if any.
+    pub index_status: Option<String>,
+    /// Change between the index and the working tree, if any.
+    pub workdir_status: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hunks: Option<Vec<GitDiffHunk>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub binary: Option<GitBinaryInfo>,
+    /// Contents and hunks were left out because the file is over
+    /// `maxFileSize`.
+    pub truncated: bool,
+    /// Set when the path is a submodule; its contents are never inlined.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub submodule: Option<GitSubmoduleChange>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GitDiffResponse {
+    pub root: String,
+    /// The commit the working tree was compared against, when diffing
+    /// against a base branch.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub base_commit: Option<String>,
+    pub files: Vec<GitDiffFile>,
+    /// Changed paths left out by `excludePaths`.
+    pub skipped: Vec<String>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GitDiffSummaryFile {
+    pub path: String,
+    pub old_path: Option<String>,
+    pub status: String,
+    pub old_size: u64,
+    pub new_size: u64,
+    pub added: usize,
+    pub removed: usize,
+    pub is_binary: bool,
+    /// Over `maxFileSize`: listed without numstat, as the full diff lists it
+    /// without contents.
+    pub truncated: bool,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GitDiffSummaryResponse {
+    pub root: String,
+    pub files: Vec<GitDiffSummaryFile>,
+    /// Changed paths left out by `excludePaths`.
+    pub skipped: Vec<String>,
+}
+
+pub fn delta_status_label(status: Delta) -> &'static str {
+    match status {
+        Delta::Added => "added",
+        Delta::Modified => "modified",
+        Delta::Deleted => "deleted",
+        Delta::Renamed => "renamed",
+        Delta::Copied => "copied",
+        Delta::Typechange => "typechange",
+        Delta::Untracked => "untracked",
+        Delta::Conflicted => "conflicted",
+        Delta::Ignored => "ignored",
+        Delta::Unreadable => "unreadable",
+        _ => "unknown",
+    }
+}
+
+fn index_status_label(status: Status) -> Option<&'static str> {
+    if status.is_conflicted() {
+        Some("conflicted")
+    } else if status.is_index_new() {
+        Some("added")
+    } else if status.is_index_modified() {
+        Some("modified")
+    } else if status.is_index_deleted() {
+        Some("deleted")
+    } else if status.is_index_renamed() {
+        Some("renamed")
+    } else if status.is_index_typechange() {
+        Some("typechange")
+    } else {
+        None
+    }
+}
+
+fn workdir_status_label(status: Status) -> Option<&'static str> {
+    if status.is_conflicted() {
+        Some("conflicted")
+    } else if status.is_wt_new() {
+        Some("untracked")
+    } else if status.is_wt_modified() {
+        Some("modified")
+    } else if status.is_wt_deleted() {
+        Some("deleted")
+    } else if status.is_wt_renamed() {
+        Some("renamed")
+    } else if status.is_wt_typechange() {
+        Some("typechange")
+    } else {
+        None
+    }
+}
+
+fn status_map(repo: &Repository) -> Result<HashMap<String, Status>, String> {
+    let mut status_opts = StatusOptions::new();
+    status_opts
+        .include_untracked(true)
+        .recurse_untracked_dirs(true);
+    let statuses = repo
+        .statuses(Some(&mut status_opts))
+        .map_err(|error| error.to_string())?;
+    Ok(statuses
+        .iter()
+        .filter_map(|entry| Some((entry.path()?.to_string(), entry.status())))
+        .collect())
+}
+
+pub fn read_blob_content(repo: &Repository, oid: Oid) -> Option<Vec<u8>> {
+    if oid.is_zero() {
+        return None;
+    }
+    repo.find_blob(oid).ok().map(|blob| blob.content().to_vec())
+}
+
+pub fn read_workdir_content(root: &Path, rel_path: &str) -> Option<Vec<u8>> {
+    let path = root.join(rel_path);
+    std::fs::read(path).ok()
+}
+
+pub fn is_binary_content(bytes: &[u8]) -> bool {
+    bytes.contains(&0)
+}
+
+/// Options shared by every working-tree diff the panel shows.
+pub fn workdir_diff_options(options: &GitDiffRequestOptions) -> DiffOptions {
+    let include_untracked = options.include_untracked.unwrap_or(true);
+    let include_ignored = options.include_ignored.unwrap_or(false);
+    let mut diff_opts = DiffOptions::new();
+    diff_opts
+        .include_untracked(include_untracked)
+        .recurse_untracked_dirs(include_untracked)
+        .include_ignored(include_ignored)
+        .recurse_ignored_dirs(include_ignored)
+        .include_typechange(true)
+        .context_lines(options.context_lines.unwrap_or(DEFAULT_CONTEXT_LINES));
+    if options.mode() == GitDiffMode::Hunks {
+        diff_opts.show_untracked_content(true);
+    }
+    match options.whitespace.unwrap_or_default() {
+        GitWhitespaceMode::None => {}
+        GitWhitespaceMode::Eol => {
+            diff_opts.ignore_whitespace_eol(true);
+        }
+        GitWhitespaceMode::Change => {
+            diff_opts.ignore_whitespace_change(true);
+        }
+        GitWhitespaceMode::All => {
+            diff_opts.ignore_whitespace(true);
+        }
+    }
+    for path in options.include_paths.iter().flatten() {
+        diff_opts.pathspec(path);
+    }
+    diff_opts
+}
+
+/// Runs rename/copy detection so moved files show up as one entry.
+pub fn find_renames(diff: &mut Diff, options: &GitDiffRequestOptions) -> Result<(), String> {
+    let renames = options.detect_renames.unwrap_or(true);
+    let copies = options.detect_copies.unwrap_or(false);
+    if !renames && !copies {
+        return Ok(());
+    }
+    let threshold = options
+        .similarity_threshold
+        .unwrap_or(DEFAULT_SIMILARITY_THRESHOLD)
+        .min(100);
+    let mut find_opts = DiffFindOptions::new();
+    find_opts
+        .renames(renames)
+        .copies(copies)
+        .for_untracked(true)
+        .rename_threshold(threshold)
+        .copy_threshold(threshold);
+    diff.find_similar(Some(&mut find_opts))
+        .map_err(|error| error.to_string())
+}
+
+/// Diffs the working tree (with index) against `base_branch` (its tip, or
+/// the merge base in three-dot mode), or against HEAD when no base is given.
+pub fn workdir_diff<'repo>(
+    repo: &'repo Repository,
+    base_branch: Option<&str>,
+    options: &GitDiffRequestOptions,
+    diff_opts: &mut DiffOptions,
+) -> Result<Diff<'repo>, String> {
+    let base_tree = match base_branch {
+        Some(base_branch) => Some(
+            comparison_commit(repo, base_branch, options)?
+                .tree()
+                .map_err(|error| error.to_string())?,
+        ),
+        None => repo.head().ok().and_then(|head| head.peel_to_tree().ok()),
+    };
+    tree_workdir_diff(repo, base_tree.as_ref(), options, diff_opts)
+}
+
+/// Diffs the working tree (with index) against `base_tree`, honouring the
+/// requested scope.
+pub fn tree_workdir_diff<'repo>(
+    repo: &'repo Repository,
+    base_tree: Option<&Tree>,
+    options: &GitDiffRequestOptions,
+    diff_opts: &mut DiffOptions,
+) -> Result<Diff<'repo>, String> {
+    let mut diff = match (options.scope.unwrap_or_default(), base_tree) {
+        (GitDiffScope::All, Some(tree)) => {
+            repo.diff_tree_to_workdir_with_index(Some(tree), Some(diff_opts))
+        }
+        (GitDiffScope::Staged, tree) => repo.diff_tree_to_index(tree, None, Some(diff_opts)),
+        (GitDiffScope::All, None) | (GitDiffScope::Unstaged, _) => {
+            repo.diff_index_to_workdir(None, Some(diff_opts))
+        }
+    }
+    .map_err(|error| error.to_string())?;
+    find_renames(&mut diff, options)?;
+    Ok(diff)
+}
+
+/// The commit a base-branch diff compares against under `options`.
+pub fn comparison_commit<'repo>(
+    repo: &'repo Repository,
+    base_branch: &str,
+    options: &GitDiffRequestOptions,
+) -> Result<Commit<'repo>, String> {
+    if options.three_dot.unwrap_or(false) {
+        merge_base_commit(repo, base_branch)
+    } else {
+        crate::resolve_base_commit(repo, base_branch)
+    }
+}
+
+/// The commit where HEAD diverged from `base_branch` (`base...HEAD`), so
+/// commits that landed on the base later are not counted against the task.
+pub fn merge_base_commit<'repo>(
+    repo: &'repo Repository,
+    base_branch: &str,
+) -> Result<Commit<'repo>, String> {
+    let base = crate::resolve_base_commit(repo, base_branch)?;
+    let Some(head) = repo.head().ok().and_then(|head| head.target()) else {
+        return Ok(base);
+    };
+    let merge_base = repo
+        .merge_base(base.id(), head)
+        .map_err(|_| format!("'{}' and HEAD have no common ancestor", base_branch))?;
+    repo.find_commit(merge_base)
+        .map_err(|error| error.to_string())
+}
+
+fn line_kind(origin: char) -> Option<&'static str> {
+    match origin {
+        ' ' => Some("context"),
+        '+' => Some("add"),
+        '-' => Some("delete"),
+        // '=', '>' and '<' only flag a missing newline at end of file.
+        _ => None,
+    }
+}
+
+/// Pairs each block of deleted lines with the added lines that follow it,
+/// line by line, and records the changed spans on both sides.
+fn mark_intraline(lines: &mut [GitDiffLine], granularity: GitIntralineGranularity) {
+    let mut index = 0;
+    while index < lines.len() {
+        if lines[index].kind != "delete" {
+            index += 1;
+            continue;
+        }
+        let deletes = index;
+        while index < lines.len() && lines[index].kind == "delete" {
+            index += 1;
+        }
+        let adds = index;
+        while index < lines.len() && lines[index].kind == "add" {
+            index += 1;
+        }
+        for offset in 0..(adds - deletes).min(index - adds) {
+            let (old, new) = (deletes + offset, adds + offset);
+            if let Some((old_spans, new_spans)) = git_intraline::intraline_spans(
+                &lines[old].content,
+                &lines[new].content,
+                granularity,
+            ) {
+                lines[old].changes = Some(old_spans);
+                lines[new].changes = Some(new_spans);
+            }
+        }
+    }
+}
+
+fn collect_hunks(
+    patch: &Patch,
+    intraline: Option<GitIntralineGranularity>,
+) -> Result<Vec<GitDiffHunk>, String> {
+    let mut hunks = Vec::new();
+    for hunk_index in 0..patch.num_hunks() {
+        let (hunk, line_count) = patch.hunk(hunk_index).map_err(|error| error.to_string())?;
+        let mut lines = Vec::with_capacity(line_count);
+        for line_index in 0..line_count {
+            let line = patch
+                .line_in_hunk(hunk_index, line_index)
+                .map_err(|error| error.to_string())?;
+            let Some(kind) = line_kind(line.origin()) else {
+                continue;
+            };
+            let content = String::from_utf8_lossy(line.content());
+            lines.push(GitDiffLine {
+                kind,
+                old_line_no: line.old_lineno(),
+                new_line_no: line.new_lineno(),
+                content: content.trim_end_matches(['\n', '\r']).to_string(),
+                changes: None,
+            });
+        }
+        if let Some(granularity) = intraline {
+            mark_intraline(&mut lines, granularity);
+        }
+        hunks.push(GitDiffHunk {
+            header: String::from_utf8_lossy(hunk.header())
+                .trim_end()
+                .to_string(),
+            old_start: hunk.old_start(),
+            old_lines: hunk.old_lines(),
+            new_start: hunk.new_start(),
+            new_lines: hunk.new_lines(),
+            lines,
+        });
+    }
+    Ok(hunks)
+}
+
+/// Reads both sides of a delta, each at its own path for renames and copies.
+/// The old side always comes from the object database. The new side is the
+/// index blob for staged diffs and the working-tree file otherwise.
+fn read_delta_contents(
+    repo: &Repository,
+    root: &Path,
+    delta: &DiffDelta,
+    path: &str,
+) -> (Option<Vec<u8>>, Option<Vec<u8>>) {
+    let old_bytes = read_blob_content(repo, delta.old_file().id());
+    let new_bytes = match delta.status() {
+        Delta::Deleted => None,
+        _ => read_blob_content(repo, delta.new_file().id())
+            .or_else(|| read_workdir_content(root, path)),
+    };
+    (old_bytes, new_bytes)
+}
+
+fn binary_info(
+    delta: &DiffDelta,
+    path: &str,
+    old_bytes: Option<&[u8]>,
+    new_bytes: Option<&[u8]>,
+    options: &GitDiffRequestOptions,
+) -> GitBinaryInfo {
+    git_binary::binary_info(
+        path,
+        (delta.old_file().id(), old_bytes),
+        (delta.new_file().id(), new_bytes),
+        options.thumbnail_max_bytes(),
+    )
+}
+
+fn diff_file_with_hunks(
+    repo: &Repository,
+    root: &Path,
+    diff: &Diff,
+    index: usize,
+    path: String,
+    old_path: Option<String>,
+    options: &GitDiffRequestOptions,
+) -> Result<GitDiffFile, String> {
+    let delta = diff
+        .get_delta(index)
+        .ok_or_else(|| "missing diff delta".to_string())?;
+    let patch = Patch::from_diff(diff, index).map_err(|error| error.to_string())?;
+    let is_binary = patch
+        .as_ref()
+        .map(|patch| patch.delta().flags().is_binary())
+        .unwrap_or_else(|| delta.flags().is_binary());
+    let hunks = match &patch {
+        Some(patch) if !is_binary => collect_hunks(patch, options.intraline)?,
+        _ => Vec::new(),
+    };
+    let binary = if is_binary {
+        let (old_bytes, new_bytes) = read_delta_contents(repo, root, &delta, &path);
+        Some(binary_info(
+            &delta,
+            &path,
+            old_bytes.as_deref(),
+            new_bytes.as_deref(),
+            options,
+        ))
+    } else {
+        None
+    };
+    Ok(GitDiffFile {
+        path,
+        old_path,
+        status: delta_status_label(delta.status()).to_string(),
+        old_content: String::new(),
+        new_content: String::new(),
+        is_binary,
+        index_status: None,
+        workdir_status: None,
+        hunks: Some(hunks),
+        binary,
+        truncated: false,
+        submodule: None,
+    })
+}
+
+fn diff_file_with_contents(
+    repo: &Repository,
+    root: &Path,
+    diff: &Diff,
+    index: usize,
+    path: String,
+    old_path: Option<String>,
+    options: &GitDiffRequestOptions,
+) -> Result<GitDiffFile, String> {
+    let delta = diff
+        .get_delta(index)
+        .ok_or_else(|| "missing diff delta".to_string())?;
+    let (old_bytes, new_bytes) = read_delta_contents(repo, root, &delta, &path);
+
+    let old_is_binary = old_bytes
+        .as_ref()
+        .map(|bytes| is_binary_content(bytes))
+        .unwrap_or(false);
+    let new_is_binary = new_bytes
+        .as_ref()
+        .map(|bytes| is_binary_content(bytes))
+        .unwrap_or(false);
+    let is_binary = old_is_binary || new_is_binary;
+
+    let old_content = if is_binary {
+        String::new()
+    } else {
+        old_bytes
+            .as_ref()
+            .map(|bytes| String::from_utf8_lossy(bytes).to_string())
+            .unwrap_or_default()
+    };
+    let new_content = if is_binary {
+        String::new()
+    } else {
+        new_bytes
+            .as_ref()
+            .map(|bytes| String::from_utf8_lossy(bytes).to_string())
+            .unwrap_or_default()
+    };
+    let binary = is_binary.then(|| {
+        binary_info(
+            &delta,
+            &path,
+            old_bytes.as_deref(),
+            new_bytes.as_deref(),
+            options,
+        )
+    });
+
+    Ok(GitDiffFile {
+        path,
+        old_path,
+        status: delta_status_label(delta.status()).to_string(),
+        old_content,
+        new_content,
+        is_binary,
+        index_status: None,
+        workdir_status: None,
+        hunks: None,
+        binary,
+        truncated: false,
+        submodule: None,
+    })
+}
+
+/// Returns the delta's path and, for renames and copies, its source path.
+fn delta_paths(delta: &DiffDelta) -> Option<(String, Option<String>)> {
+    let old_path = delta
+        .old_file()
+        .path()
+        .map(|path| path.to_string_lossy().to_string());
+    let path = delta
+        .new_file()
+        .path()
+        .map(|path| path.to_string_lossy().to_string())
+        .or_else(|| old_path.clone())?;
+    let old_path = old_path.filter(|old_path| *old_path != path);
+    Some((path, old_path))
+}
+
+/// Finds the delta for `path`, matching either side of a rename.
+pub fn find_delta_index(diff: &Diff, path: &str) -> Option<usize> {
+    diff.deltas().position(|delta| {
+        delta_paths(&delta)
+            .map(|(new_path, old_path)| new_path == path || old_path.as_deref() == Some(path))
+            .unwrap_or(false)
+    })
+}
+
+/// Lists changed paths with their sizes and numstat, without shipping any
+/// file contents. Applies `exclude_paths` and `max_file_size` the way the
+/// full diff does, returning the excluded paths alongside.
+pub fn summarize_diff(
+    repo: &Repository,
+    diff: &Diff,
+    options: &GitDiffRequestOptions,
+) -> Result<(Vec<GitDiffSummaryFile>, Vec<String>), String> {
+    let excluded = exclude_pathspec(options)?;
+    let mut files = Vec::new();
+    let mut skipped = Vec::new();
+    for (index, delta) in diff.deltas().enumerate() {
+        let Some((path, old_path)) = delta_paths(&delta) else {
+            continue;
+        };
+        if is_excluded(excluded.as_ref(), &path, &old_path) {
+            skipped.push(path);
+            continue;
+        }
+        let old_size = file_size(repo, &delta.old_file());
+        let new_size = file_size(repo, &delta.new_file());
+        if options
+            .max_file_size
+            .is_some_and(|limit| old_size.max(new_size) > limit)
+        {
+            files.push(GitDiffSummaryFile {
+                path,
+                old_path,
+                status: delta_status_label(delta.status()).to_string(),
+                old_size,
+                new_size,
+                added: 0,
+                removed: 0,
+                is_binary: delta.flags().is_binary(),
+                truncated: true,
+            });
+            continue;
+        }
+        let patch = Patch::from_diff(diff, index).map_err(|error| error.to_string())?;
+        let is_binary = patch
+            .as_ref()
+            .map(|patch| patch.delta().flags().is_binary())
+            .unwrap_or_else(|| delta.flags().is_binary());
+        let (added, removed) = match &patch {
+            Some(patch) if !is_binary => {
+                let (_, added, removed) = patch.line_stats().map_err(|error| error.to_string())?;
+                (added, removed)
+            }
+            _ => (0, 0),
+        };
+        files.push(GitDiffSummaryFile {
+            path,
+            old_path,
+            status: delta_status_label(delta.status()).to_string(),
+            old_size,
+            new_size,
+            added,
+            removed,
+            is_binary,
+            truncated: false,
+        });
+    }
+    Ok((files, skipped))
+}
+
+/// Builds the panel payload for a single delta of a tree/index-to-workdir diff.
+pub fn collect_diff_file(
+    repo: &Repository,
+    root: &Path,
+    diff: &Diff,
+    index: usize,
+    options: &GitDiffRequestOptions,
+) -> Result<Option<GitDiffFile>, String> {
+    let status = diff
+        .get_delta(index)
+        .as_ref()
+        .and_then(delta_paths)
+        .and_then(|(path, _)| repo.status_file(Path::new(&path)).ok())
+        .unwrap_or(Status::CURRENT);
+    build_diff_file(repo, root, diff, index, options, |_| status)
+}
+
+/// An entry that lists the delta without any file contents.
+fn contentless_file(
+    delta: &DiffDelta,
+    path: &str,
+    old_path: &Option<String>,
+    options: &GitDiffRequestOptions,
+) -> GitDiffFile {
+    GitDiffFile {
+        path: path.to_string(),
+        old_path: old_path.clone(),
+        status: delta_status_label(delta.status()).to_string(),
+        old_content: String::new(),
+        new_content: String::new(),
+        is_binary: delta.flags().is_binary(),
+        index_status: None,
+        workdir_status: None,
+        hunks: (options.mode() == GitDiffMode::Hunks).then(Vec::new),
+        binary: None,
+        truncated: false,
+        submodule: None,
+    }
+}
+
+/// Size of one side of a delta. libgit2 leaves it at 0 for tree-side
+/// entries, so those are read from the object database header.
+fn file_size(repo: &Repository, file: &DiffFile) -> u64 {
+    if file.size() > 0 || file.id().is_zero() {
+        return file.size();
+    }
+    repo.odb()
+        .and_then(|odb| odb.read_header(file.id()))
+        .map(|(size, _)| size as u64)
+        .unwrap_or(0)
+}
+
+/// Handles the deltas that must not be loaded: submodules, described by
+/// their commits, and files over `max_file_size`, checked before any content
+/// is read.
+fn special_file(
+    repo: &Repository,
+    diff: &Diff,
+    index: usize,
+    path: &str,
+    old_path: &Option<String>,
+    options: &GitDiffRequestOptions,
+) -> Result<Option<GitDiffFile>, String> {
+    let Some(delta) = diff.get_delta(index) else {
+        return Ok(None);
+    };
+    if git_submodule::is_submodule_delta(&delta) {
+        let mut file = contentless_file(&delta, path, old_path, options);
+        file.is_binary = false;
+        file.submodule = Some(git_submodule::submodule_change(
+            repo, &delta, path, options,
+        )?);
+        return Ok(Some(file));
+    }
+    match options.max_file_size {
+        Some(limit)
+            if file_size(repo, &delta.old_file()).max(file_size(repo, &delta.new_file()))
+                > limit =>
+        {
+            let mut file = contentless_file(&delta, path, old_path, options);
+            file.truncated = true;
+            Ok(Some(file))
+        }
+        _ => Ok(None),
+    }
+}
+
+fn build_diff_file(
+    repo: &Repository,
+    root: &Path,
+    diff: &Diff,
+    index: usize,
+    options: &GitDiffRequestOptions,
+    status_of: impl Fn(&str) -> Status,
+) -> Result<Option<GitDiffFile>, String> {
+    let Some((path, old_path)) = diff.get_delta(index).as_ref().and_then(delta_paths) else {
+        return Ok(None);
+    };
+    let status = status_of(&path);
+    let mut file = if let Some(file) = special_file(repo, diff, index, &path, &old_path, options)? {
+        file
+    } else {
+        match options.mode() {
+            GitDiffMode::Full => {
+                diff_file_with_contents(repo, root, diff, index, path, old_path, options)?
+            }
+            GitDiffMode::Hunks => {
+                diff_file_with_hunks(repo, root, diff, index, path, old_path, options)?
+            }
+        }
+    };
+    file.index_status = index_status_label(status).map(str::to_string);
+    file.workdir_status = workdir_status_label(status).map(str::to_string);
+    Ok(Some(file))
+}
+
+fn exclude_pathspec(options: &GitDiffRequestOptions) -> Result<Option<Pathspec>, String> {
+    match options.exclude_paths.as_deref() {
+        Some(paths) if !paths.is_empty() => Pathspec::new(paths)
+            .map(Some)
+            .map_err(|error| error.to_string()),
+        _ => Ok(None),
+    }
+}
+
+/// Whether `exclude_paths` matches either side of a delta.
+fn is_excluded(excluded: Option<&Pathspec>, path: &str, old_path: &Option<String>) -> bool {
+    let Some(pathspec) = excluded else {
+        return false;
+    };
+    let matches = |path: &str| pathspec.matches_path(Path::new(path), PathspecFlags::DEFAULT);
+    matches(path) || old_path.as_deref().is_some_and(matches)
+}
+
+/// Builds the payload for every delta, leaving out (and listing) the paths
+/// `exclude_paths` matches on either side of a rename.
+fn collect_files(
+    repo: &Repository,
+    root: &Path,
+    diff: &Diff,
+    options: &GitDiffRequestOptions,
+    status_of: impl Fn(&str) -> Status,
+) -> Result<(Vec<GitDiffFile>, Vec<String>), String> {
+    let excluded = exclude_pathspec(options)?;
+    let mut files = Vec::new();
+    let mut skipped = Vec::new();
+    for index in 0..diff.deltas().len() {
+        if let Some((path, old_path)) = diff.get_delta(index).as_ref().and_then(delta_paths) {
+            if is_excluded(excluded.as_ref(), &path, &old_path) {
+                skipped.push(path);
+                continue;
+            }
+        }
+        if let Some(file) = build_diff_file(repo, root, diff, index, options, &status_of)? {
+            files.push(file);
+        }
+    }
+    Ok((files, skipped))
+}
+
+/// Turns a tree/index-to-workdir diff into the payload the diff panel
+/// renders, along with the paths `exclude_paths` left out.
+pub fn collect_diff_files(
+    repo: &Repository,
+    root: &Path,
+    diff: &Diff,
+    options: &GitDiffRequestOptions,
+) -> Result<(Vec<GitDiffFile>, Vec<String>), String> {
+    let statuses = status_map(repo)?;
+    collect_files(repo, root, diff, options, |path: &str| {
+        statuses.get(path).copied().unwrap_or(Status::CURRENT)
+    })
+}
+
+/// Turns a tree-to-tree diff into the diff panel payload. Both sides come
+/// from the object database, so no index or working-tree status is reported.
+pub fn collect_tree_diff_files(
+    repo: &Repository,
+    root: &Path,
+    diff: &Diff,
+    options: &GitDiffRequestOptions,
+) -> Result<Vec<GitDiffFile>, String> {
+    collect_files(repo, root, diff, options, |_| Status::CURRENT).map(|(files, _)| files)
+}
@@ -0,0 +1,356 @@
+use crate::git_index::{apply_selection, open_repo, relative_path, GitLineSelection};
+use git2::{Repository, StatusOptions};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use tauri::Manager;
+
+#[derive(Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GitDiscardTarget {
+    /// Drop unstaged changes, keeping what is staged.
+    #[default]
+    Index,
+    /// Drop every change since the last commit. Only the working tree is
+    /// touched; staged content stays in the index.
+    Head,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GitDiscardResponse {
+    /// Directory holding the pre-discard copies, if anything was backed up.
+    pub backup_path: Option<String>,
+    pub restored: Vec<String>,
+    pub deleted: Vec<String>,
+    /// Untracked files left alone because `deleteUntracked` was not set, and
+    /// submodules, which are never touched.
+    pub skipped: Vec<String>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct BackupManifest<'a> {
+    repo: String,
+    target: &'a str,
+    created_at: i64,
+    files: &'a [String],
+}
+
+/// Copies the current working-tree versions of `files` into a fresh directory
+/// under `~/.prompter/discard-backups/`, next to a `manifest.json` recording
+/// where they came from.
+fn backup_files(
+    app: &tauri::AppHandle,
+    root: &Path,
+    files: &[String],
+    target: GitDiscardTarget,
+) -> Result<Option<PathBuf>, String> {
+    // Only regular files are copied; following a symlink here would back up
+    // whatever it points at.
+    let existing: Vec<String> = files
+        .iter()
+        .filter(|file| {
+            std::fs::symlink_metadata(root.join(file))
+                .map(|metadata| metadata.is_file())
+                .unwrap_or(false)
+        })
+        .cloned()
+        .collect();
+    if existing.is_empty() {
+        return Ok(None);
+    }
+
+    let home_dir = app.path().home_dir().map_err(|error| error.to_string())?;
+    let created_at = chrono::Utc::now().timestamp_millis();
+    let mut backup_dir = home_dir
+        .join(".prompter")
+        .join("discard-backups")
+        .join(created_at.to_string());
+    let mut attempt = 1;
+    while backup_dir.exists() {
+        backup_dir.set_file_name(format!("{}-{}", created_at, attempt));
+        attempt += 1;
+    }
+
+    for file in &existing {
+        let destination = backup_dir.join("files").join(file);
+        if let Some(parent) = destination.parent() {
+            std::fs::create_dir_all(parent).map_err(|error| error.to_string())?;
+        }
+        std::fs::copy(root.join(file), &destination).map_err(|error| error.to_string())?;
+    }
+    let manifest = BackupManifest {
+        repo: root.to_string_lossy().to_string(),
+        target: match target {
+            GitDiscardTarget::Index => "index",
+            GitDiscardTarget::Head => "head",
+        },
+        created_at,
+        files: &existing,
+    };
+    let manifest = serde_json::to_string_pretty(&manifest).map_err(|error| error.to_string())?;
+    std::fs::write(backup_dir.join("manifest.json"), manifest)
+        .map_err(|error| error.to_string())?;
+    Ok(Some(backup_dir))
+}
+
+/// Content and file mode of `rel_path` in the index or HEAD, or `None` when
+/// the path is not tracked there.
+fn target_entry(
+    repo: &Repository,
+    rel_path: &Path,
+    target: GitDiscardTarget,
+) -> Result<Option<(Vec<u8>, u32)>, String> {
+    let (id, mode) = match target {
+        GitDiscardTarget::Index => {
+            let index = repo.index().map_err(|error| error.to_string())?;
+            match index.get_path(rel_path, 0) {
+                Some(entry) => (entry.id, entry.mode),
+                None => return Ok(None),
+            }
+        }
+        GitDiscardTarget::Head => {
+            let Ok(head) = repo.head() else {
+                return Ok(None);
+            };
+            let tree = head.peel_to_tree().map_err(|error| error.to_string())?;
+            match tree.get_path(rel_path) {
+                Ok(entry) => (entry.id(), entry.filemode() as u32),
+                Err(_) => return Ok(None),
+            }
+        }
+    };
+    let blob = repo.find_blob(id).map_err(|error| error.to_string())?;
+    Ok(Some((blob.content().to_vec(), mode)))
+}
+
+const MODE_SYMLINK: u32 = 0o120000;
+const MODE_GITLINK: u32 = 0o160000;
+
+fn is_symlink(path: &Path) -> bool {
+    std::fs::symlink_metadata(path)
+        .map(|metadata| metadata.file_type().is_symlink())
+        .unwrap_or(false)
+}
+
+/// Writes a blob back to the working tree. An existing symlink at `path` is
+/// replaced rather than followed, and symlink entries are recreated as links
+/// (as files holding the target where links are unavailable, like git does).
+fn write_workdir_file(path: &Path, content: &[u8], mode: u32) -> Result<(), String> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(|error| error.to_string())?;
+    }
+    if is_symlink(path) {
+        std::fs::remove_file(path).map_err(|error| error.to_string())?;
+    }
+    #[cfg(unix)]
+    if mode == MODE_SYMLINK {
+        use std::os::unix::ffi::OsStrExt;
+        if path.exists() {
+            std::fs::remove_file(path).map_err(|error| error.to_string())?;
+        }
+        let target = std::ffi::OsStr::from_bytes(content);
+        return std::os::unix::fs::symlink(target, path).map_err(|error| error.to_string());
+    }
+    std::fs::write(path, content).map_err(|error| error.to_string())?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let executable = mode == 0o100755;
+        let mut permissions = std::fs::metadata(path)
+            .map_err(|error| error.to_string())?
+            .permissions();
+        let current = permissions.mode();
+        let updated = if executable {
+            current | ((current & 0o444) >> 2)
+        } else {
+            current & !0o111
+        };
+        if updated != current {
+            permissions.set_mode(updated);
+            std::fs::set_permissions(path, permissions).map_err(|error| error.to_string())?;
+        }
+    }
+    #[cfg(not(unix))]
+    let _ = mode;
+    Ok(())
+}
+
+fn remove_workdir_file(root: &Path, rel_path: &Path) -> Result<(), String> {
+    let path = root.join(rel_path);
+    std::fs::remove_file(&path).map_err(|error| error.to_string())?;
+    // Clean up directories the untracked file leaves empty, never going past
+    // the working-tree root.
+    let mut parent = path.parent();
+    while let Some(dir) = parent {
+        if dir == root || !dir.starts_with(root) || std::fs::remove_dir(dir).is_err() {
+            break;
+        }
+        parent = dir.parent();
+    }
+    Ok(())
+}
+
+/// Changed and untracked files under the directory `rel_path`, so discarding
+/// a directory goes through the same per-file restore, delete and backup as
+/// listing its files would.
+fn changed_files_under(repo: &Repository, rel_path: &Path) -> Result<Vec<PathBuf>, String> {
+    let mut options = StatusOptions::new();
+    options
+        .include_untracked(true)
+        .recurse_untracked_dirs(true)
+        .include_ignored(false);
+    let statuses = repo
+        .statuses(Some(&mut options))
+        .map_err(|error| error.to_string())?;
+    Ok(statuses
+        .iter()
+        .filter_map(|entry| entry.path().map(PathBuf::from))
+        .filter(|path| path.starts_with(rel_path))
+        .collect())
+}
+
+fn to_display_path(rel_path: &Path) -> String {
+    rel_path.to_string_lossy().replace('\\', "/")
+}
+
+#[tauri::command]
+pub fn git_discard(
+    path: String,
+    paths: Vec<String>,
+    target: Option<GitDiscardTarget>,
+    delete_untracked: Option<bool>,
+    app: tauri::AppHandle,
+) -> Result<GitDiscardResponse, String> {
+    let repo = open_repo(&path)?;
+    let root = repo
+        .workdir()
+        .unwrap_or_else(|| Path::new(""))
+        .to_path_buf();
+    let target = target.unwrap_or_default();
+    let delete_untracked = delete_untracked.unwrap_or(false);
+
+    let mut plan = Vec::new();
+    let mut skipped = Vec::new();
+    let mut rel_paths = Vec::new();
+    for file_path in &paths {
+        let rel_path = relative_path(&root, file_path)?;
+        let is_dir = std::fs::symlink_metadata(root.join(&rel_path))
+            .map(|metadata| metadata.is_dir())
+            .unwrap_or(false);
+        if is_dir && !root.join(&rel_path).join(".git").exists() {
+            rel_paths.extend(changed_files_under(&repo, &rel_path)?);
+        } else {
+            rel_paths.push(rel_path);
+        }
+    }
+    for rel_path in rel_paths {
+        let entry = target_entry(&repo, &rel_path, target)?;
+        // Submodule contents are not ours to restore or delete.
+        let is_gitlink = entry
+            .as_ref()
+            .is_some_and(|(_, mode)| *mode == MODE_GITLINK)
+            || root.join(&rel_path).join(".git").exists();
+        if is_gitlink || (entry.is_none() && !delete_untracked) {
+            skipped.push(to_display_path(&rel_path));
+            continue;
+        }
+        plan.push((rel_path, entry));
+    }
+
+    let files: Vec<String> = plan
+        .iter()
+        .map(|(rel_path, _)| to_display_path(rel_path))
+        .collect();
+    let backup_path = backup_files(&app, &root, &files, target)?;
+
+    let mut restored = Vec::new();
+    let mut deleted = Vec::new();
+    for (rel_path, entry) in plan {
+        match entry {
+            Some((content, mode)) => {
+                write_workdir_file(&root.join(&rel_path), &content, mode)?;
+                restored.push(to_display_path(&rel_path));
+            }
+            None => {
+                let is_file = std::fs::symlink_metadata(root.join(&rel_path))
+                    .map(|metadata| !metadata.is_dir())
+                    .unwrap_or(false);
+                if is_file {
+                    remove_workdir_file(&root, &rel_path)?;
+                    deleted.push(to_display_path(&rel_path));
+                }
+            }
+        }
+    }
+
+    Ok(GitDiscardResponse {
+        backup_path: backup_path.map(|path| path.to_string_lossy().to_string()),
+        restored,
+        deleted,
+        skipped,
+    })
+}
+
+#[tauri::command]
+pub fn git_discard_selection(
+    path: String,
+    file_path: String,
+    selection: GitLineSelection,
+    target: Option<GitDiscardTarget>,
+    app: tauri::AppHandle,
+) -> Result<GitDiscardResponse, String> {
+    let repo = open_repo(&path)?;
+    let root = repo
+        .workdir()
+        .unwrap_or_else(|| Path::new(""))
+        .to_path_buf();
+    let target = target.unwrap_or_default();
+    let rel_path = relative_path(&root, &file_path)?;
+
+    let entry = target_entry(&repo, &rel_path, target)?;
+    if is_symlink(&root.join(&rel_path))
+        || entry
+            .as_ref()
+            .is_some_and(|(_, mode)| *mode == MODE_SYMLINK || *mode == MODE_GITLINK)
+    {
+        return Err(format!(
+            "'{}' is a symlink or submodule; discard it as a whole",
+            file_path
+        ));
+    }
+    let workdir = std::fs::read(root.join(&rel_path)).ok();
+    let old = entry
+        .as_ref()
+        .map(|(content, _)| content.as_slice())
+        .unwrap_or_default();
+    let new = workdir.as_deref().unwrap_or_default();
+    let result = apply_selection(old, new, &selection, true)?;
+
+    let display_path = to_display_path(&rel_path);
+    let backup_path = backup_files(&app, &root, std::slice::from_ref(&display_path), target)?;
+    let mut response = GitDiscardResponse {
+        backup_path: backup_path.map(|path| path.to_string_lossy().to_string()),
+        restored: Vec::new(),
+        deleted: Vec::new(),
+        skipped: Vec::new(),
+    };
+
+    // Discarding every line of an untracked file removes the file.
+    if entry.is_none() && result.is_empty() {
+        if workdir.is_some() {
+            remove_workdir_file(&root, &rel_path)?;
+            response.deleted.push(display_path);
+        }
+        return Ok(response);
+    }
+    if workdir.is_some() {
+        // Rewriting in place keeps the file's current permissions.
+        std::fs::write(root.join(&rel_path), &result).map_err(|error| error.to_string())?;
+    } else {
+        let mode = entry.map(|(_, mode)| mode).unwrap_or(0o100644);
+        write_workdir_file(&root.join(&rel_path), &result, mode)?;
+    }
+    response.restored.push(display_path);
+    Ok(response)
+}
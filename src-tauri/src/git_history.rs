@@ -0,0 +1,355 @@
+use crate::git_log::GitLogCommit;
+use crate::{resolve_section_path, run_git_command_output};
+use git2::{BlameOptions, DiffOptions, ErrorCode, Oid, Patch, Repository};
+use serde::Serialize;
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use std::path::Path;
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GitBlameLine {
+    pub line_no: usize,
+    pub content: String,
+    /// `None` for lines that are not committed yet.
+    pub commit_id: Option<String>,
+    pub author_name: Option<String>,
+    pub author_email: Option<String>,
+    /// Author time in milliseconds since the Unix epoch.
+    pub time: Option<i64>,
+    pub summary: Option<String>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GitSectionHistoryResponse {
+    pub path: String,
+    pub start_line: usize,
+    pub end_line: usize,
+    pub lines: Vec<GitBlameLine>,
+    /// Commits that changed the range, newest first, as `git log -L` reports
+    /// them for the matching lines of HEAD's version of the file. Insertions
+    /// and deletions are counted within the range only.
+    pub commits: Vec<GitLogCommit>,
+}
+
+const RECORD_SEPARATOR: char = '\u{1e}';
+const FIELD_SEPARATOR: char = '\u{1f}';
+
+/// Runs `git log -L` over the committed version of the range and reads the
+/// commit headers, counting the range's own +/- lines from each patch.
+fn range_history(
+    root: &str,
+    rel_path: &str,
+    start: usize,
+    end: usize,
+) -> Result<Vec<GitLogCommit>, String> {
+    let range = format!("-L{},{}:{}", start, end, rel_path);
+    let format = format!(
+        "--format={}%H{}%an{}%ae{}%at{}%s",
+        RECORD_SEPARATOR, FIELD_SEPARATOR, FIELD_SEPARATOR, FIELD_SEPARATOR, FIELD_SEPARATOR
+    );
+    let output = run_git_command_output(root, &["log", "--no-color", &format, &range])?;
+
+    let mut commits: Vec<GitLogCommit> = Vec::new();
+    for line in output.lines() {
+        if let Some(header) = line.strip_prefix(RECORD_SEPARATOR) {
+            let fields: Vec<&str> = header.splitn(5, FIELD_SEPARATOR).collect();
+            if fields.len() < 5 {
+                continue;
+            }
+            let id = fields[0].to_string();
+            commits.push(GitLogCommit {
+                short_id: id.chars().take(7).collect(),
+                id,
+                summary: fields[4].to_string(),
+                author_name: fields[1].to_string(),
+                author_email: fields[2].to_string(),
+                time: fields[3].parse::<i64>().unwrap_or(0) * 1000,
+                files_changed: 1,
+                insertions: 0,
+                deletions: 0,
+            });
+            continue;
+        }
+        let Some(commit) = commits.last_mut() else {
+            continue;
+        };
+        if line.starts_with('+') && !line.starts_with("+++") {
+            commit.insertions += 1;
+        } else if line.starts_with('-') && !line.starts_with("---") {
+            commit.deletions += 1;
+        }
+    }
+    Ok(commits)
+}
+
+/// Maps a working-tree line range onto HEAD's version of the file, through
+/// the uncommitted changes. Lines past the end of HEAD's version are dropped,
+/// since `git log -L` rejects them. Returns `None` when no line of the range
+/// is in HEAD (the file or every line in the range is new).
+fn head_line_range(
+    repo: &Repository,
+    rel_path: &Path,
+    content: &[u8],
+    start: usize,
+    end: usize,
+) -> Result<Option<(usize, usize)>, String> {
+    let Some(tree) = repo.head().ok().and_then(|head| head.peel_to_tree().ok()) else {
+        return Ok(None);
+    };
+    let entry = match tree.get_path(rel_path) {
+        Ok(entry) => entry,
+        Err(error) if error.code() == ErrorCode::NotFound => return Ok(None),
+        Err(error) => return Err(error.to_string()),
+    };
+    let blob = repo
+        .find_blob(entry.id())
+        .map_err(|error| error.to_string())?;
+    let head_line_count = blob
+        .content()
+        .split_inclusive(|byte| *byte == b'\n')
+        .count();
+    let mut diff_opts = DiffOptions::new();
+    diff_opts.context_lines(0);
+    let patch = Patch::from_blob_and_buffer(
+        &blob,
+        Some(rel_path),
+        content,
+        Some(rel_path),
+        Some(&mut diff_opts),
+    )
+    .map_err(|error| error.to_string())?;
+
+    // Hunks as (new start, new count, old count), plus the lines added in
+    // the working tree, which have no counterpart in HEAD.
+    let mut hunks = Vec::new();
+    let mut added = std::collections::HashSet::new();
+    for hunk_index in 0..patch.num_hunks() {
+        let (hunk, line_count) = patch.hunk(hunk_index).map_err(|error| error.to_string())?;
+        hunks.push((
+            hunk.new_start() as usize,
+            hunk.new_lines() as usize,
+            hunk.old_lines() as usize,
+        ));
+        for line_index in 0..line_count {
+            let line = patch
+                .line_in_hunk(hunk_index, line_index)
+                .map_err(|error| error.to_string())?;
+            if let Some(line_no) = line.new_lineno() {
+                added.insert(line_no as usize);
+            }
+        }
+    }
+
+    let head_lines: Vec<usize> = (start..=end)
+        .filter(|line_no| !added.contains(line_no))
+        .map(|line_no| {
+            // Every hunk that ends before the line shifts it by the lines
+            // that hunk removed or added.
+            hunks
+                .iter()
+                .filter(|(new_start, new_lines, _)| line_no >= new_start + (*new_lines).max(1))
+                .fold(line_no, |head_line, (_, new_lines, old_lines)| {
+                    head_line + old_lines - new_lines
+                })
+        })
+        .filter(|head_line| *head_line <= head_line_count)
+        .collect();
+    Ok(head_lines
+        .first()
+        .zip(head_lines.last())
+        .map(|(first, last)| (*first, *last)))
+}
+
+/// Blames `line_start..=line_end` of the working-tree file and lists the
+/// commits that touched that range.
+#[tauri::command]
+pub fn get_file_section_history(
+    root_path: Option<String>,
+    file_path: String,
+    line_start: usize,
+    line_end: usize,
+) -> Result<GitSectionHistoryResponse, String> {
+    let path = resolve_section_path(root_path, file_path)?;
+    let repo =
+        Repository::discover(path.parent().unwrap_or(&path)).map_err(|error| error.to_string())?;
+    let workdir = repo
+        .workdir()
+        .ok_or_else(|| "repository has no working directory".to_string())?;
+    let workdir = workdir.canonicalize().map_err(|error| error.to_string())?;
+    let path = path.canonicalize().map_err(|error| error.to_string())?;
+    let rel_path = path
+        .strip_prefix(&workdir)
+        .map_err(|_| "file is outside the repository".to_string())?;
+    let rel_display = rel_path.to_string_lossy().replace('\\', "/");
+
+    let content = std::fs::read(&path).map_err(|error| error.to_string())?;
+    let text = String::from_utf8_lossy(&content);
+    let file_lines: Vec<&str> = text.lines().collect();
+    let start = line_start.max(1);
+    let end = line_end.max(start).min(file_lines.len().max(start));
+
+    // Blame the committed file, then re-blame the working copy on top so
+    // uncommitted edits show up as such. A file that was never committed has
+    // no blame at all and every line counts as uncommitted.
+    let mut blame_opts = BlameOptions::new();
+    let committed = match repo.blame_file(rel_path, Some(&mut blame_opts)) {
+        Ok(blame) => Some(blame),
+        Err(error) if error.code() == ErrorCode::NotFound => None,
+        Err(error) => return Err(error.to_string()),
+    };
+    let blame = committed
+        .as_ref()
+        .map(|committed| committed.blame_buffer(&content))
+        .transpose()
+        .map_err(|error| error.to_string())?;
+
+    let mut summaries: HashMap<Oid, (String, String, i64, String)> = HashMap::new();
+    let mut lines = Vec::new();
+    for line_no in start..=end {
+        let Some(line) = file_lines.get(line_no - 1) else {
+            break;
+        };
+        let commit_id = blame
+            .as_ref()
+            .and_then(|blame| blame.get_line(line_no))
+            .map(|hunk| hunk.final_commit_id())
+            .filter(|id| !id.is_zero());
+        let details = match commit_id {
+            Some(id) => {
+                if let Entry::Vacant(entry) = summaries.entry(id) {
+                    let commit = repo.find_commit(id).map_err(|error| error.to_string())?;
+                    let author = commit.author();
+                    entry.insert((
+                        author.name().unwrap_or_default().to_string(),
+                        author.email().unwrap_or_default().to_string(),
+                        author.when().seconds() * 1000,
+                        commit.summary().unwrap_or_default().to_string(),
+                    ));
+                }
+                summaries.get(&id)
+            }
+            None => None,
+        };
+        lines.push(GitBlameLine {
+            line_no,
+            content: line.to_string(),
+            commit_id: commit_id.map(|id| id.to_string()),
+            author_name: details.map(|details| details.0.clone()),
+            author_email: details.map(|details| details.1.clone()),
+            time: details.map(|details| details.2),
+            summary: details.map(|details| details.3.clone()),
+        });
+    }
+
+    // `git log -L` addresses HEAD's version of the file, so the range is
+    // translated through the uncommitted edits first.
+    let root = workdir.to_string_lossy().to_string();
+    let commits = match head_line_range(&repo, rel_path, &content, start, end)? {
+        Some((head_start, head_end)) => range_history(&root, &rel_display, head_start, head_end)?,
+        None => Vec::new(),
+    };
+
+    Ok(GitSectionHistoryResponse {
+        path: rel_display,
+        start_line: start,
+        end_line: end,
+        lines,
+        commits,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use git2::Signature;
+    use std::path::PathBuf;
+
+    /// A repository with `f.txt` committed as `a` to `e`, deleted on drop.
+    struct TestRepo {
+        dir: PathBuf,
+        repo: Repository,
+    }
+
+    impl TestRepo {
+        fn new() -> TestRepo {
+            static COUNTER: std::sync::atomic::AtomicUsize = std::sync::atomic::AtomicUsize::new(0);
+            let dir = std::env::temp_dir().join(format!(
+                "prompter-history-test-{}-{}",
+                std::process::id(),
+                COUNTER.fetch_add(1, std::sync::atomic::Ordering::Relaxed)
+            ));
+            let _ = std::fs::remove_dir_all(&dir);
+            let repo = Repository::init(&dir).unwrap();
+            {
+                let blob = repo.blob(b"a\nb\nc\nd\ne\n").unwrap();
+                let mut tree = repo.treebuilder(None).unwrap();
+                tree.insert("f.txt", blob, 0o100644).unwrap();
+                let tree = repo.find_tree(tree.write().unwrap()).unwrap();
+                let signature = Signature::now("Test", "test@example.com").unwrap();
+                repo.commit(Some("HEAD"), &signature, &signature, "init", &tree, &[])
+                    .unwrap();
+            }
+            TestRepo { dir, repo }
+        }
+
+        fn range(&self, content: &str, start: usize, end: usize) -> Option<(usize, usize)> {
+            head_line_range(
+                &self.repo,
+                Path::new("f.txt"),
+                content.as_bytes(),
+                start,
+                end,
+            )
+            .unwrap()
+        }
+    }
+
+    impl Drop for TestRepo {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.dir);
+        }
+    }
+
+    const HEAD: &str = "a\nb\nc\nd\ne\n";
+
+    #[test]
+    fn maps_the_first_and_last_lines() {
+        let repo = TestRepo::new();
+        assert_eq!(repo.range(HEAD, 1, 1), Some((1, 1)));
+        assert_eq!(repo.range(HEAD, 5, 5), Some((5, 5)));
+        assert_eq!(repo.range(HEAD, 1, 5), Some((1, 5)));
+    }
+
+    #[test]
+    fn shifts_lines_past_uncommitted_edits() {
+        let repo = TestRepo::new();
+        let inserted = "new\na\nb\nc\nd\ne\n";
+        assert_eq!(repo.range(inserted, 1, 1), None);
+        assert_eq!(repo.range(inserted, 1, 2), Some((1, 1)));
+        assert_eq!(repo.range(inserted, 6, 6), Some((5, 5)));
+
+        let removed = "b\nc\nd\ne\n";
+        assert_eq!(repo.range(removed, 1, 1), Some((2, 2)));
+        assert_eq!(repo.range(removed, 4, 4), Some((5, 5)));
+
+        let appended = "a\nb\nc\nd\ne\nf\ng\n";
+        assert_eq!(repo.range(appended, 5, 7), Some((5, 5)));
+        assert_eq!(repo.range(appended, 6, 7), None);
+    }
+
+    #[test]
+    fn drops_lines_past_the_end_of_head() {
+        let repo = TestRepo::new();
+        assert_eq!(repo.range(HEAD, 4, 10), Some((4, 5)));
+        assert_eq!(repo.range(HEAD, 6, 8), None);
+    }
+
+    #[test]
+    fn skips_files_missing_from_head() {
+        let repo = TestRepo::new();
+        let range = head_line_range(&repo.repo, Path::new("new.txt"), b"x\n", 1, 1).unwrap();
+        assert_eq!(range, None);
+    }
+}
@@ -0,0 +1,464 @@
+use git2::{DiffOptions, IndexAddOption, IndexEntry, IndexTime, Patch, Repository};
+use serde::Deserialize;
+use std::path::{Component, Path, PathBuf};
+
+const DEFAULT_CONTEXT_LINES: u32 = 3;
+
+#[derive(Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GitLineSide {
+    Old,
+    New,
+}
+
+/// Inclusive range of changed lines. Deletions are matched by their old line
+/// number, additions by their new line number.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GitLineRange {
+    pub side: GitLineSide,
+    pub start: u32,
+    pub end: u32,
+}
+
+/// Part of a file's changes to act on. Hunk indexes and line numbers refer to
+/// the diff being applied: index→workdir when staging or discarding into the
+/// index, HEAD→index when unstaging, HEAD→workdir when discarding to HEAD.
+#[derive(Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GitLineSelection {
+    pub hunks: Option<Vec<usize>>,
+    pub lines: Option<Vec<GitLineRange>>,
+    pub context_lines: Option<u32>,
+}
+
+impl GitLineSelection {
+    fn selects(&self, hunk_index: usize, side: GitLineSide, line_no: Option<u32>) -> bool {
+        if let Some(hunks) = &self.hunks {
+            if hunks.contains(&hunk_index) {
+                return true;
+            }
+        }
+        let (Some(lines), Some(line_no)) = (&self.lines, line_no) else {
+            return false;
+        };
+        lines
+            .iter()
+            .any(|range| range.side == side && range.start <= line_no && line_no <= range.end)
+    }
+}
+
+fn split_lines(bytes: &[u8]) -> Vec<&[u8]> {
+    bytes.split_inclusive(|byte| *byte == b'\n').collect()
+}
+
+/// Appends a line, first ending the previous one if it was a last line
+/// without a trailing newline that is no longer last.
+fn push_line(output: &mut Vec<u8>, line: &[u8]) {
+    if output.last().is_some_and(|byte| *byte != b'\n') {
+        output.push(b'\n');
+    }
+    output.extend_from_slice(line);
+}
+
+/// Rebuilds `old` with only part of the `old` → `new` changes applied. With
+/// `revert` set, the selected changes are the ones left out instead, which
+/// turns the same selection into an unstage/discard of those lines.
+pub fn apply_selection(
+    old: &[u8],
+    new: &[u8],
+    selection: &GitLineSelection,
+    revert: bool,
+) -> Result<Vec<u8>, String> {
+    let mut diff_opts = DiffOptions::new();
+    diff_opts.context_lines(selection.context_lines.unwrap_or(DEFAULT_CONTEXT_LINES));
+    let patch = Patch::from_buffers(old, None, new, None, Some(&mut diff_opts))
+        .map_err(|error| error.to_string())?;
+    // Binary changes have no lines to pick from.
+    if patch.delta().flags().is_binary() {
+        return Err("cannot select lines of a binary file".to_string());
+    }
+
+    let old_lines = split_lines(old);
+    let mut output = Vec::with_capacity(new.len().max(old.len()));
+    let mut cursor = 0usize;
+
+    for hunk_index in 0..patch.num_hunks() {
+        let (hunk, line_count) = patch.hunk(hunk_index).map_err(|error| error.to_string())?;
+        // A hunk that only adds lines starts *after* its old_start line.
+        let hunk_start = if hunk.old_lines() == 0 {
+            hunk.old_start() as usize
+        } else {
+            (hunk.old_start() as usize).saturating_sub(1)
+        };
+        while cursor < hunk_start && cursor < old_lines.len() {
+            push_line(&mut output, old_lines[cursor]);
+            cursor += 1;
+        }
+
+        for line_index in 0..line_count {
+            let line = patch
+                .line_in_hunk(hunk_index, line_index)
+                .map_err(|error| error.to_string())?;
+            match line.origin() {
+                ' ' => {
+                    if let Some(old_line) = old_lines.get(cursor) {
+                        push_line(&mut output, old_line);
+                    }
+                    cursor += 1;
+                }
+                '-' => {
+                    let apply = selection.selects(hunk_index, GitLineSide::Old, line.old_lineno())
+                        != revert;
+                    if !apply {
+                        if let Some(old_line) = old_lines.get(cursor) {
+                            push_line(&mut output, old_line);
+                        }
+                    }
+                    cursor += 1;
+                }
+                '+' => {
+                    let apply = selection.selects(hunk_index, GitLineSide::New, line.new_lineno())
+                        != revert;
+                    if apply {
+                        push_line(&mut output, line.content());
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    while cursor < old_lines.len() {
+        push_line(&mut output, old_lines[cursor]);
+        cursor += 1;
+    }
+    Ok(output)
+}
+
+fn head_blob(repo: &Repository, rel_path: &Path) -> Result<Option<Vec<u8>>, String> {
+    let Ok(head) = repo.head() else {
+        return Ok(None);
+    };
+    let tree = head.peel_to_tree().map_err(|error| error.to_string())?;
+    let Ok(entry) = tree.get_path(rel_path) else {
+        return Ok(None);
+    };
+    let blob = repo
+        .find_blob(entry.id())
+        .map_err(|error| error.to_string())?;
+    Ok(Some(blob.content().to_vec()))
+}
+
+fn index_blob(repo: &Repository, rel_path: &Path) -> Result<Option<Vec<u8>>, String> {
+    let index = repo.index().map_err(|error| error.to_string())?;
+    let Some(entry) = index.get_path(rel_path, 0) else {
+        return Ok(None);
+    };
+    let blob = repo
+        .find_blob(entry.id)
+        .map_err(|error| error.to_string())?;
+    Ok(Some(blob.content().to_vec()))
+}
+
+fn new_index_entry(root: &Path, rel_path: &Path) -> IndexEntry {
+    let mut mode = 0o100644;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        if let Ok(metadata) = std::fs::metadata(root.join(rel_path)) {
+            if metadata.permissions().mode() & 0o111 != 0 {
+                mode = 0o100755;
+            }
+        }
+    }
+    #[cfg(not(unix))]
+    let _ = root;
+    let path = rel_path.to_string_lossy().replace('\\', "/").into_bytes();
+    IndexEntry {
+        ctime: IndexTime::new(0, 0),
+        mtime: IndexTime::new(0, 0),
+        dev: 0,
+        ino: 0,
+        mode,
+        uid: 0,
+        gid: 0,
+        file_size: 0,
+        id: git2::Oid::zero(),
+        flags: path.len().min(0xFFF) as u16,
+        flags_extended: 0,
+        path,
+    }
+}
+
+/// Writes `content` as the staged version of `rel_path`, or drops the path
+/// from the index when `content` is `None`.
+pub fn write_index_content(
+    repo: &Repository,
+    rel_path: &Path,
+    content: Option<&[u8]>,
+) -> Result<(), String> {
+    let root = repo
+        .workdir()
+        .ok_or_else(|| "repository has no working directory".to_string())?;
+    let mut index = repo.index().map_err(|error| error.to_string())?;
+    match content {
+        Some(content) => {
+            let entry = index
+                .get_path(rel_path, 0)
+                .unwrap_or_else(|| new_index_entry(root, rel_path));
+            index
+                .add_frombuffer(&entry, content)
+                .map_err(|error| error.to_string())?;
+        }
+        None => {
+            if index.get_path(rel_path, 0).is_some() {
+                index
+                    .remove_path(rel_path)
+                    .map_err(|error| error.to_string())?;
+            }
+        }
+    }
+    index.write().map_err(|error| error.to_string())
+}
+
+/// Resolves `file_path` (absolute, or relative to `root`) to a path inside
+/// the working tree. `..` components and paths that lead out of the tree
+/// through a symlinked directory are rejected before anything touches the
+/// filesystem.
+pub fn relative_path(root: &Path, file_path: &str) -> Result<PathBuf, String> {
+    let outside = || format!("'{}' is outside the repository", file_path);
+    let path = Path::new(file_path);
+    let rel_path = if path.is_absolute() {
+        path.strip_prefix(root)
+            .map(Path::to_path_buf)
+            .map_err(|_| outside())?
+    } else {
+        path.to_path_buf()
+    };
+    if rel_path.as_os_str().is_empty()
+        || rel_path
+            .components()
+            .any(|component| !matches!(component, Component::Normal(_) | Component::CurDir))
+    {
+        return Err(outside());
+    }
+
+    let canonical_root = root.canonicalize().map_err(|error| error.to_string())?;
+    let mut existing = root.join(&rel_path);
+    while !existing.exists() {
+        match existing.parent() {
+            Some(parent) => existing = parent.to_path_buf(),
+            None => break,
+        }
+    }
+    // The path itself may be a symlink, which is fine; where its directory
+    // resolves to is what matters.
+    let existing = if existing == root.join(&rel_path) {
+        existing.parent().map(Path::to_path_buf).unwrap_or(existing)
+    } else {
+        existing
+    };
+    let canonical = existing.canonicalize().map_err(|error| error.to_string())?;
+    if !canonical.starts_with(&canonical_root) {
+        return Err(outside());
+    }
+    Ok(rel_path)
+}
+
+pub fn open_repo(path: &str) -> Result<Repository, String> {
+    let repo = Repository::discover(path).map_err(|error| error.to_string())?;
+    if repo.workdir().is_none() {
+        return Err("repository has no working directory".to_string());
+    }
+    Ok(repo)
+}
+
+#[tauri::command]
+pub fn git_stage(path: String, paths: Vec<String>) -> Result<(), String> {
+    // An empty pathspec matches the whole tree.
+    if paths.is_empty() {
+        return Err("no paths to stage".to_string());
+    }
+    let repo = open_repo(&path)?;
+    let mut index = repo.index().map_err(|error| error.to_string())?;
+    // add_all picks up new and modified files, update_all records deletions.
+    index
+        .add_all(paths.iter(), IndexAddOption::DEFAULT, None)
+        .map_err(|error| error.to_string())?;
+    index
+        .update_all(paths.iter(), None)
+        .map_err(|error| error.to_string())?;
+    index.write().map_err(|error| error.to_string())
+}
+
+#[tauri::command]
+pub fn git_unstage(path: String, paths: Vec<String>) -> Result<(), String> {
+    if paths.is_empty() {
+        return Err("no paths to unstage".to_string());
+    }
+    let repo = open_repo(&path)?;
+    let head = repo.head().ok().and_then(|head| head.peel_to_commit().ok());
+    // Without a HEAD commit, resetting drops the paths from the index.
+    repo.reset_default(head.as_ref().map(|commit| commit.as_object()), paths.iter())
+        .map_err(|error| error.to_string())
+}
+
+#[tauri::command]
+pub fn git_stage_selection(
+    path: String,
+    file_path: String,
+    selection: GitLineSelection,
+) -> Result<(), String> {
+    let repo = open_repo(&path)?;
+    let root = repo.workdir().unwrap_or_else(|| Path::new(""));
+    let rel_path = relative_path(root, &file_path)?;
+
+    let staged = index_blob(&repo, &rel_path)?;
+    let workdir = std::fs::read(root.join(&rel_path)).ok();
+    let old = staged.as_deref().unwrap_or_default();
+    let new = workdir.as_deref().unwrap_or_default();
+    let result = apply_selection(old, new, &selection, false)?;
+
+    // Staging every line of a deleted file stages the deletion itself.
+    let content = if workdir.is_none() && result.is_empty() {
+        None
+    } else if staged.is_none() && result.is_empty() && !new.is_empty() {
+        return Ok(());
+    } else {
+        Some(result.as_slice())
+    };
+    write_index_content(&repo, &rel_path, content)
+}
+
+#[tauri::command]
+pub fn git_unstage_selection(
+    path: String,
+    file_path: String,
+    selection: GitLineSelection,
+) -> Result<(), String> {
+    let repo = open_repo(&path)?;
+    let root = repo.workdir().unwrap_or_else(|| Path::new(""));
+    let rel_path = relative_path(root, &file_path)?;
+
+    let committed = head_blob(&repo, &rel_path)?;
+    let staged = index_blob(&repo, &rel_path)?;
+    let old = committed.as_deref().unwrap_or_default();
+    let new = staged.as_deref().unwrap_or_default();
+    let result = apply_selection(old, new, &selection, true)?;
+
+    // Unstaging every line of a newly added file removes it from the index.
+    let content = if committed.is_none() && result.is_empty() {
+        None
+    } else {
+        Some(result.as_slice())
+    };
+    write_index_content(&repo, &rel_path, content)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines(ranges: &[(GitLineSide, u32, u32)]) -> GitLineSelection {
+        GitLineSelection {
+            lines: Some(
+                ranges
+                    .iter()
+                    .map(|&(side, start, end)| GitLineRange { side, start, end })
+                    .collect(),
+            ),
+            ..GitLineSelection::default()
+        }
+    }
+
+    fn apply(old: &str, new: &str, selection: &GitLineSelection, revert: bool) -> String {
+        let result = apply_selection(old.as_bytes(), new.as_bytes(), selection, revert).unwrap();
+        String::from_utf8(result).unwrap()
+    }
+
+    #[test]
+    fn applies_selected_lines_of_a_hunk() {
+        let selection = lines(&[(GitLineSide::New, 3, 3)]);
+        assert_eq!(
+            apply("a\nb\n", "a\nx\ny\nb\n", &selection, false),
+            "a\ny\nb\n"
+        );
+
+        let selection = lines(&[(GitLineSide::Old, 2, 2), (GitLineSide::New, 2, 2)]);
+        assert_eq!(
+            apply("a\nb\nc\n", "a\nB\nC\n", &selection, false),
+            "a\nc\nB\n"
+        );
+    }
+
+    #[test]
+    fn applies_selected_hunks() {
+        let selection = GitLineSelection {
+            hunks: Some(vec![1]),
+            context_lines: Some(0),
+            ..GitLineSelection::default()
+        };
+        let old = "a\nb\nc\nd\ne\n";
+        let new = "A\nb\nc\nd\nE\n";
+        assert_eq!(apply(old, new, &selection, false), "a\nb\nc\nd\nE\n");
+    }
+
+    #[test]
+    fn applies_pure_addition_at_file_start() {
+        let selection = GitLineSelection {
+            context_lines: Some(0),
+            ..lines(&[(GitLineSide::New, 1, 1)])
+        };
+        assert_eq!(
+            apply("b\nc\n", "a\nz\nb\nc\n", &selection, false),
+            "a\nb\nc\n"
+        );
+        let nothing = GitLineSelection {
+            context_lines: Some(0),
+            ..GitLineSelection::default()
+        };
+        assert_eq!(apply("b\nc\n", "a\nz\nb\nc\n", &nothing, false), "b\nc\n");
+    }
+
+    #[test]
+    fn keeps_missing_newline_at_end_of_file() {
+        let all = lines(&[(GitLineSide::Old, 1, 9), (GitLineSide::New, 1, 9)]);
+        assert_eq!(apply("a\nb", "a\nc", &all, false), "a\nc");
+        assert_eq!(apply("a\nb\n", "a\nb", &all, false), "a\nb");
+        assert_eq!(apply("a\nb", "a\nb\n", &all, false), "a\nb\n");
+
+        let addition_only = lines(&[(GitLineSide::New, 3, 3)]);
+        assert_eq!(apply("a\nb\n", "a\nb\nc", &addition_only, false), "a\nb\nc");
+    }
+
+    #[test]
+    fn ends_a_kept_last_line_before_following_content() {
+        let addition_only = lines(&[(GitLineSide::New, 2, 2)]);
+        assert_eq!(apply("a\nb", "a\nc", &addition_only, false), "a\nb\nc");
+
+        let keep_deletion = lines(&[(GitLineSide::Old, 2, 2)]);
+        assert_eq!(apply("a\nb", "a\nc", &keep_deletion, true), "a\nb\nc");
+    }
+
+    #[test]
+    fn reverts_selected_lines() {
+        let old = "a\nb\nc\nd\n";
+        let new = "a\nB\nc\nD\n";
+        let selection = lines(&[(GitLineSide::Old, 2, 2), (GitLineSide::New, 2, 2)]);
+        assert_eq!(apply(old, new, &selection, true), "a\nb\nc\nD\n");
+        assert_eq!(apply(old, new, &GitLineSelection::default(), true), new);
+    }
+
+    #[test]
+    fn rejects_binary_files() {
+        let selection = GitLineSelection {
+            hunks: Some(vec![0]),
+            ..GitLineSelection::default()
+        };
+        for revert in [false, true] {
+            let result = apply_selection(b"\0\x01\n", b"\0\x02\n", &selection, revert);
+            assert!(result.is_err());
+        }
+    }
+}
@@ -0,0 +1,230 @@
+use serde::{Deserialize, Serialize};
+
+/// Longest pair of token sequences compared; longer lines (minified files,
+/// generated data) are left without intraline spans.
+const MAX_COMPARED_CELLS: usize = 4_000_000;
+
+#[derive(Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GitIntralineGranularity {
+    /// Identifier-like runs, whitespace runs and single punctuation marks.
+    #[default]
+    Word,
+    Char,
+}
+
+/// A changed range within a diff line's `content`, in UTF-16 code units so
+/// it indexes JavaScript strings directly.
+#[derive(Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GitDiffSpan {
+    pub start: usize,
+    pub end: usize,
+}
+
+/// A token and its UTF-16 range in the line.
+struct Token<'a> {
+    text: &'a str,
+    start: usize,
+    end: usize,
+}
+
+fn is_word_char(ch: char) -> bool {
+    ch.is_alphanumeric() || ch == '_'
+}
+
+fn tokenize(line: &str, granularity: GitIntralineGranularity) -> Vec<Token<'_>> {
+    let mut tokens = Vec::new();
+    let mut offset = 0;
+    let mut chars = line.char_indices().peekable();
+    while let Some((byte_start, ch)) = chars.next() {
+        let mut byte_end = byte_start + ch.len_utf8();
+        let mut width = ch.len_utf16();
+        if granularity == GitIntralineGranularity::Word && (is_word_char(ch) || ch.is_whitespace())
+        {
+            let same_class = |next: char| {
+                if is_word_char(ch) {
+                    is_word_char(next)
+                } else {
+                    next.is_whitespace()
+                }
+            };
+            while let Some(&(_, next)) = chars.peek() {
+                if !same_class(next) {
+                    break;
+                }
+                byte_end += next.len_utf8();
+                width += next.len_utf16();
+                chars.next();
+            }
+        }
+        tokens.push(Token {
+            text: &line[byte_start..byte_end],
+            start: offset,
+            end: offset + width,
+        });
+        offset += width;
+    }
+    tokens
+}
+
+/// Marks the tokens of each side that are not part of the longest common
+/// subsequence.
+fn changed_tokens(old: &[Token], new: &[Token]) -> (Vec<bool>, Vec<bool>) {
+    let (rows, cols) = (old.len(), new.len());
+    let mut lengths = vec![0u32; (rows + 1) * (cols + 1)];
+    let at = |row: usize, col: usize| row * (cols + 1) + col;
+    for row in (0..rows).rev() {
+        for col in (0..cols).rev() {
+            lengths[at(row, col)] = if old[row].text == new[col].text {
+                lengths[at(row + 1, col + 1)] + 1
+            } else {
+                lengths[at(row + 1, col)].max(lengths[at(row, col + 1)])
+            };
+        }
+    }
+
+    let mut old_changed = vec![true; rows];
+    let mut new_changed = vec![true; cols];
+    let (mut row, mut col) = (0, 0);
+    while row < rows && col < cols {
+        if old[row].text == new[col].text {
+            old_changed[row] = false;
+            new_changed[col] = false;
+            row += 1;
+            col += 1;
+        } else if lengths[at(row + 1, col)] >= lengths[at(row, col + 1)] {
+            row += 1;
+        } else {
+            col += 1;
+        }
+    }
+    (old_changed, new_changed)
+}
+
+/// Joins adjacent changed tokens into spans.
+fn spans(tokens: &[Token], changed: &[bool]) -> Vec<GitDiffSpan> {
+    let mut spans: Vec<GitDiffSpan> = Vec::new();
+    for (token, _) in tokens.iter().zip(changed).filter(|(_, changed)| **changed) {
+        match spans.last_mut() {
+            Some(last) if last.end == token.start => last.end = token.end,
+            _ => spans.push(GitDiffSpan {
+                start: token.start,
+                end: token.end,
+            }),
+        }
+    }
+    spans
+}
+
+/// Computes the changed spans of a deleted line and the added line that
+/// replaced it. Returns `None` when the lines are too long to compare or
+/// share too little to make highlighting useful, in which case the whole
+/// line is the change.
+pub fn intraline_spans(
+    old: &str,
+    new: &str,
+    granularity: GitIntralineGranularity,
+) -> Option<(Vec<GitDiffSpan>, Vec<GitDiffSpan>)> {
+    let old_tokens = tokenize(old, granularity);
+    let new_tokens = tokenize(new, granularity);
+    if (old_tokens.len() + 1).saturating_mul(new_tokens.len() + 1) > MAX_COMPARED_CELLS {
+        return None;
+    }
+    let (old_changed, new_changed) = changed_tokens(&old_tokens, &new_tokens);
+
+    let unchanged: usize = old_tokens
+        .iter()
+        .zip(&old_changed)
+        .filter(|(token, changed)| !**changed && !token.text.trim().is_empty())
+        .map(|(token, _)| token.end - token.start)
+        .sum();
+    let longest = old
+        .trim()
+        .encode_utf16()
+        .count()
+        .max(new.trim().encode_utf16().count());
+    if unchanged * 3 < longest {
+        return None;
+    }
+
+    Some((
+        spans(&old_tokens, &old_changed),
+        spans(&new_tokens, &new_changed),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    type Spans = Vec<(usize, usize)>;
+
+    fn diff(old: &str, new: &str, granularity: GitIntralineGranularity) -> Option<(Spans, Spans)> {
+        let pairs =
+            |spans: Vec<GitDiffSpan>| spans.iter().map(|span| (span.start, span.end)).collect();
+        intraline_spans(old, new, granularity).map(|(old, new)| (pairs(old), pairs(new)))
+    }
+
+    fn words(old: &str, new: &str) -> Option<(Spans, Spans)> {
+        diff(old, new, GitIntralineGranularity::Word)
+    }
+
+    fn chars(old: &str, new: &str) -> Option<(Spans, Spans)> {
+        diff(old, new, GitIntralineGranularity::Char)
+    }
+
+    #[test]
+    fn marks_changed_words() {
+        assert_eq!(
+            words("let x = 1;", "let y = 1;"),
+            Some((vec![(4, 5)], vec![(4, 5)]))
+        );
+        assert_eq!(
+            words("call(first, second)", "call(first, third, second)"),
+            Some((vec![], vec![(12, 19)]))
+        );
+        assert_eq!(words("same line", "same line"), Some((vec![], vec![])));
+    }
+
+    #[test]
+    fn marks_changed_characters() {
+        assert_eq!(chars("color", "colour"), Some((vec![], vec![(4, 5)])));
+        assert_eq!(chars("grey", "gray"), Some((vec![(2, 3)], vec![(2, 3)])));
+    }
+
+    #[test]
+    fn measures_spans_in_utf16_units() {
+        // `é` is two bytes of UTF-8 but one UTF-16 unit.
+        assert_eq!(
+            words("café au lait", "café au chai"),
+            Some((vec![(8, 12)], vec![(8, 12)]))
+        );
+        assert_eq!(
+            chars("naïve ø", "naïve ö"),
+            Some((vec![(6, 7)], vec![(6, 7)]))
+        );
+    }
+
+    #[test]
+    fn counts_astral_characters_as_two_units() {
+        assert_eq!(
+            words("value = 🎉 done", "value = 🎊 done"),
+            Some((vec![(8, 10)], vec![(8, 10)]))
+        );
+        assert_eq!(
+            words("😀 a = 1", "😀 a = 2"),
+            Some((vec![(7, 8)], vec![(7, 8)]))
+        );
+        assert_eq!(chars("a😀b", "a😁b"), Some((vec![(1, 3)], vec![(1, 3)])));
+    }
+
+    #[test]
+    fn gives_up_on_unrelated_or_oversized_lines() {
+        assert_eq!(words("alpha beta", "gamma delta"), None);
+        // Identical, but past the comparison budget.
+        let long = "a".repeat(2_100);
+        assert_eq!(chars(&long, &long), None);
+        assert_eq!(words(&long, &long), Some((vec![], vec![])));
+    }
+}
@@ -0,0 +1,96 @@
+use crate::resolve_base_commit;
+use git2::{Repository, Sort};
+use serde::Serialize;
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GitLogCommit {
+    pub id: String,
+    pub short_id: String,
+    pub summary: String,
+    pub author_name: String,
+    pub author_email: String,
+    /// Author time in milliseconds since the Unix epoch.
+    pub time: i64,
+    pub files_changed: usize,
+    pub insertions: usize,
+    pub deletions: usize,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GitLogResponse {
+    pub branch: String,
+    pub merge_base: Option<String>,
+    /// Commits on the branch that are not on the base.
+    pub ahead: usize,
+    /// Commits on the base that are not on the branch.
+    pub behind: usize,
+    /// Newest first.
+    pub commits: Vec<GitLogCommit>,
+}
+
+fn log_commit(repo: &Repository, commit: &git2::Commit) -> Result<GitLogCommit, String> {
+    let tree = commit.tree().map_err(|error| error.to_string())?;
+    let parent_tree = match commit.parent(0) {
+        Ok(parent) => Some(parent.tree().map_err(|error| error.to_string())?),
+        Err(_) => None,
+    };
+    let diff = repo
+        .diff_tree_to_tree(parent_tree.as_ref(), Some(&tree), None)
+        .map_err(|error| error.to_string())?;
+    let stats = diff.stats().map_err(|error| error.to_string())?;
+    let author = commit.author();
+    let id = commit.id().to_string();
+
+    Ok(GitLogCommit {
+        short_id: id.chars().take(7).collect(),
+        id,
+        summary: commit.summary().unwrap_or_default().to_string(),
+        author_name: author.name().unwrap_or_default().to_string(),
+        author_email: author.email().unwrap_or_default().to_string(),
+        time: author.when().seconds() * 1000,
+        files_changed: stats.files_changed(),
+        insertions: stats.insertions(),
+        deletions: stats.deletions(),
+    })
+}
+
+#[tauri::command]
+pub fn get_git_log(path: String, base_branch: String) -> Result<GitLogResponse, String> {
+    let repo = Repository::discover(&path).map_err(|error| error.to_string())?;
+    let head_ref = repo.head().map_err(|error| error.to_string())?;
+    let branch = head_ref.shorthand().unwrap_or_default().to_string();
+    let head = head_ref
+        .peel_to_commit()
+        .map_err(|error| error.to_string())?;
+    let base = resolve_base_commit(&repo, &base_branch)?;
+
+    // Unrelated histories have no merge base; every commit counts as ahead.
+    let merge_base = repo.merge_base(head.id(), base.id()).ok();
+    let (ahead, behind) = repo
+        .graph_ahead_behind(head.id(), base.id())
+        .map_err(|error| error.to_string())?;
+
+    let mut revwalk = repo.revwalk().map_err(|error| error.to_string())?;
+    revwalk
+        .set_sorting(Sort::TOPOLOGICAL | Sort::TIME)
+        .map_err(|error| error.to_string())?;
+    revwalk.push(head.id()).map_err(|error| error.to_string())?;
+    revwalk.hide(base.id()).map_err(|error| error.to_string())?;
+
+    let mut commits = Vec::new();
+    for id in revwalk {
+        let id = id.map_err(|error| error.to_string())?;
+        let commit = repo.find_commit(id).map_err(|error| error.to_string())?;
+        commits.push(log_commit(&repo, &commit)?);
+    }
+
+    Ok(GitLogResponse {
+        branch,
+        merge_base: merge_base.map(|id| id.to_string()),
+        ahead,
+        behind,
+        commits,
+    })
+}
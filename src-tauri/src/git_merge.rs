@@ -0,0 +1,245 @@
+use git2::{build::CheckoutBuilder, BranchType, Index, Repository, StatusOptions};
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum GitMergeStrategy {
+    #[default]
+    MergeCommit,
+    Squash,
+    FfOnly,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum GitMergeStatus {
+    /// The base already contains the branch; nothing was done.
+    UpToDate,
+    FastForward,
+    Merged,
+    Squashed,
+    /// Dry run only: the merge would apply without conflicts.
+    Clean,
+    Conflicts,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GitMergeConflict {
+    pub path: String,
+    /// `content`, `add-add`, `modify-delete` or `delete-modify` (deleted on
+    /// the base, modified on the branch).
+    pub kind: String,
+    pub ancestor_id: Option<String>,
+    pub our_id: Option<String>,
+    pub their_id: Option<String>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GitMergeResponse {
+    pub status: GitMergeStatus,
+    pub dry_run: bool,
+    /// The new tip of the base branch when the merge was applied.
+    pub commit_id: Option<String>,
+    pub conflicts: Vec<GitMergeConflict>,
+}
+
+/// Lists the conflicts of an in-memory merge index. Shared by merge, rebase
+/// and patch application, which all report conflicts in this shape.
+pub fn collect_conflicts(index: &Index) -> Result<Vec<GitMergeConflict>, String> {
+    let mut conflicts = Vec::new();
+    for conflict in index.conflicts().map_err(|error| error.to_string())? {
+        let conflict = conflict.map_err(|error| error.to_string())?;
+        let path = [&conflict.our, &conflict.their, &conflict.ancestor]
+            .iter()
+            .find_map(|entry| entry.as_ref())
+            .map(|entry| String::from_utf8_lossy(&entry.path).to_string())
+            .unwrap_or_default();
+        let kind = match (
+            conflict.ancestor.is_some(),
+            conflict.our.is_some(),
+            conflict.their.is_some(),
+        ) {
+            (false, _, _) => "add-add",
+            (true, true, false) => "modify-delete",
+            (true, false, true) => "delete-modify",
+            _ => "content",
+        };
+        let id_of =
+            |entry: &Option<git2::IndexEntry>| entry.as_ref().map(|entry| entry.id.to_string());
+        conflicts.push(GitMergeConflict {
+            path,
+            kind: kind.to_string(),
+            ancestor_id: id_of(&conflict.ancestor),
+            our_id: id_of(&conflict.our),
+            their_id: id_of(&conflict.their),
+        });
+    }
+    Ok(conflicts)
+}
+
+/// Fails when tracked files in the checkout have uncommitted changes, so a
+/// merge never mixes with (or overwrites) someone's work in progress.
+pub fn ensure_clean_checkout(repo: &Repository) -> Result<(), String> {
+    let mut status_opts = StatusOptions::new();
+    status_opts.include_untracked(false).include_ignored(false);
+    let statuses = repo
+        .statuses(Some(&mut status_opts))
+        .map_err(|error| error.to_string())?;
+    if let Some(entry) = statuses.iter().next() {
+        return Err(format!(
+            "the checkout has uncommitted changes ({}); commit or stash them first",
+            entry.path().unwrap_or_default()
+        ));
+    }
+    Ok(())
+}
+
+fn response(status: GitMergeStatus, dry_run: bool) -> GitMergeResponse {
+    GitMergeResponse {
+        status,
+        dry_run,
+        commit_id: None,
+        conflicts: Vec::new(),
+    }
+}
+
+#[tauri::command]
+pub fn merge_task_branch(
+    repo_path: String,
+    branch: String,
+    base_branch: String,
+    strategy: Option<GitMergeStrategy>,
+    message: Option<String>,
+    dry_run: Option<bool>,
+) -> Result<GitMergeResponse, String> {
+    let strategy = strategy.unwrap_or_default();
+    let dry_run = dry_run.unwrap_or(false);
+    let repo = Repository::discover(&repo_path).map_err(|error| error.to_string())?;
+
+    let base_ref = repo
+        .find_branch(&base_branch, BranchType::Local)
+        .map_err(|_| format!("base branch '{}' not found", base_branch))?
+        .into_reference();
+    let base_ref_name = base_ref
+        .name()
+        .ok_or_else(|| "base branch name is not valid UTF-8".to_string())?
+        .to_string();
+    let base = base_ref
+        .peel_to_commit()
+        .map_err(|error| error.to_string())?;
+    let theirs = repo
+        .find_branch(&branch, BranchType::Local)
+        .map_err(|_| format!("branch '{}' not found", branch))?
+        .into_reference()
+        .peel_to_commit()
+        .map_err(|error| error.to_string())?;
+
+    if !dry_run {
+        let head = repo.head().map_err(|error| error.to_string())?;
+        if head.name() != Some(base_ref_name.as_str()) {
+            return Err(format!(
+                "the main checkout is on '{}', not '{}'; check out the base branch first",
+                head.shorthand().unwrap_or("a detached HEAD"),
+                base_branch
+            ));
+        }
+        ensure_clean_checkout(&repo)?;
+    }
+
+    if base.id() == theirs.id()
+        || repo
+            .graph_descendant_of(base.id(), theirs.id())
+            .map_err(|error| error.to_string())?
+    {
+        return Ok(response(GitMergeStatus::UpToDate, dry_run));
+    }
+    let can_fast_forward = repo
+        .graph_descendant_of(theirs.id(), base.id())
+        .map_err(|error| error.to_string())?;
+
+    if strategy == GitMergeStrategy::FfOnly {
+        if !can_fast_forward {
+            return Err(format!(
+                "'{}' has diverged from '{}'; a fast-forward merge is not possible",
+                branch, base_branch
+            ));
+        }
+        if dry_run {
+            return Ok(response(GitMergeStatus::FastForward, true));
+        }
+        let tree = theirs.tree().map_err(|error| error.to_string())?;
+        repo.checkout_tree(tree.as_object(), Some(CheckoutBuilder::new().safe()))
+            .map_err(|error| error.to_string())?;
+        repo.reference(
+            &base_ref_name,
+            theirs.id(),
+            true,
+            &format!("merge {}: Fast-forward", branch),
+        )
+        .map_err(|error| error.to_string())?;
+        let mut result = response(GitMergeStatus::FastForward, false);
+        result.commit_id = Some(theirs.id().to_string());
+        return Ok(result);
+    }
+
+    // Merge in memory first so conflicts never touch the checkout.
+    let mut index = repo
+        .merge_commits(&base, &theirs, None)
+        .map_err(|error| error.to_string())?;
+    if index.has_conflicts() {
+        let mut result = response(GitMergeStatus::Conflicts, dry_run);
+        result.conflicts = collect_conflicts(&index)?;
+        return Ok(result);
+    }
+    if dry_run {
+        return Ok(response(GitMergeStatus::Clean, true));
+    }
+
+    let tree_id = index
+        .write_tree_to(&repo)
+        .map_err(|error| error.to_string())?;
+    let tree = repo.find_tree(tree_id).map_err(|error| error.to_string())?;
+    // The identity is only needed once a commit is certain; conflicts, dry
+    // runs and fast-forwards must keep working without user.name/email.
+    let signature = repo.signature().map_err(|error| error.to_string())?;
+    let (status, parents, default_message) = match strategy {
+        GitMergeStrategy::Squash => (
+            GitMergeStatus::Squashed,
+            vec![&base],
+            format!("Squash merge branch '{}' into {}", branch, base_branch),
+        ),
+        _ => (
+            GitMergeStatus::Merged,
+            vec![&base, &theirs],
+            format!("Merge branch '{}' into {}", branch, base_branch),
+        ),
+    };
+    let message = message
+        .filter(|message| !message.trim().is_empty())
+        .unwrap_or(default_message);
+    let commit_id = repo
+        .commit(None, &signature, &signature, &message, &tree, &parents)
+        .map_err(|error| error.to_string())?;
+
+    // Safe checkout refuses to overwrite local edits, in which case the base
+    // branch is left where it was.
+    repo.checkout_tree(tree.as_object(), Some(CheckoutBuilder::new().safe()))
+        .map_err(|error| error.to_string())?;
+    repo.reference(
+        &base_ref_name,
+        commit_id,
+        true,
+        &format!(
+            "merge {}: {}",
+            branch,
+            message.lines().next().unwrap_or_default()
+        ),
+    )
+    .map_err(|error| error.to_string())?;
+
+    let mut result = response(status, false);
+    result.commit_id = Some(commit_id.to_string());
+    Ok(result)
+}
@@ -0,0 +1,241 @@
+use crate::git_diff::{self, GitDiffRequestOptions};
+use crate::{resolve_base_commit, run_git_command_output};
+use git2::{Diff, DiffFormat, Patch, Repository};
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+use tauri_plugin_dialog::DialogExt;
+
+#[derive(Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GitPatchFormat {
+    /// One unified diff of the working tree (untracked files included)
+    /// against the point where the task branched off the base branch.
+    #[default]
+    Diff,
+    /// `git format-patch` mbox of the commits since the base branch.
+    Mbox,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GitPatchExportResponse {
+    pub content: String,
+    pub path: Option<String>,
+}
+
+/// The task's changes since it branched off `base_branch`, so commits that
+/// landed on the base afterwards are not reverted by the patch.
+fn task_diff<'repo>(repo: &'repo Repository, base_branch: &str) -> Result<Diff<'repo>, String> {
+    let options = GitDiffRequestOptions {
+        three_dot: Some(true),
+        ..GitDiffRequestOptions::default()
+    };
+    let mut diff_opts = git_diff::workdir_diff_options(&options);
+    diff_opts.show_untracked_content(true).show_binary(true);
+    git_diff::workdir_diff(repo, Some(base_branch), &options, &mut diff_opts)
+}
+
+/// Renders the task's changes since `base_branch` as a patch `git apply`
+/// understands, binary files included.
+pub fn unified_diff(repo: &Repository, base_branch: &str) -> Result<String, String> {
+    let diff = task_diff(repo, base_branch)?;
+    let mut patch = Vec::new();
+    diff.print(DiffFormat::Patch, |_, _, line| {
+        if matches!(line.origin(), '+' | '-' | ' ') {
+            patch.push(line.origin() as u8);
+        }
+        patch.extend_from_slice(line.content());
+        true
+    })
+    .map_err(|error| error.to_string())?;
+    Ok(String::from_utf8_lossy(&patch).to_string())
+}
+
+#[tauri::command]
+pub async fn export_task_patch(
+    path: String,
+    base_branch: String,
+    format: Option<GitPatchFormat>,
+    destination: Option<String>,
+    save_dialog: Option<bool>,
+    app: tauri::AppHandle,
+) -> Result<GitPatchExportResponse, String> {
+    // Rendering the patch, the save dialog and the write all block; keep them
+    // off the async runtime's workers.
+    tauri::async_runtime::spawn_blocking(move || {
+        export_patch(&path, &base_branch, format, destination, save_dialog, &app)
+    })
+    .await
+    .map_err(|error| error.to_string())?
+}
+
+fn export_patch(
+    path: &str,
+    base_branch: &str,
+    format: Option<GitPatchFormat>,
+    destination: Option<String>,
+    save_dialog: Option<bool>,
+    app: &tauri::AppHandle,
+) -> Result<GitPatchExportResponse, String> {
+    let format = format.unwrap_or_default();
+    let repo = Repository::discover(path).map_err(|error| error.to_string())?;
+    let branch = repo
+        .head()
+        .ok()
+        .and_then(|head| head.shorthand().map(str::to_string))
+        .unwrap_or_else(|| "task".to_string());
+
+    let (content, extension) = match format {
+        GitPatchFormat::Diff => (unified_diff(&repo, base_branch)?, "diff"),
+        GitPatchFormat::Mbox => {
+            let base = resolve_base_commit(&repo, base_branch)?;
+            let range = format!("{}..HEAD", base.id());
+            let content = run_git_command_output(path, &["format-patch", "--stdout", &range])?;
+            if content.is_empty() {
+                return Err(format!(
+                    "no commits on '{}' since '{}'",
+                    branch, base_branch
+                ));
+            }
+            (content, "mbox")
+        }
+    };
+
+    let target = match destination.filter(|value| !value.trim().is_empty()) {
+        Some(path) => Some(PathBuf::from(path)),
+        None if save_dialog.unwrap_or(false) => app
+            .dialog()
+            .file()
+            .set_title("Export task changes")
+            .set_file_name(format!("{}.{}", branch.replace('/', "-"), extension))
+            .add_filter(extension, &[extension, "patch"])
+            .blocking_save_file()
+            .map(|path| path.into_path().map_err(|error| error.to_string()))
+            .transpose()?,
+        None => None,
+    };
+
+    if let Some(target) = &target {
+        std::fs::write(target, &content).map_err(|error| error.to_string())?;
+    }
+
+    Ok(GitPatchExportResponse {
+        content,
+        path: target.map(|path| path.to_string_lossy().to_string()),
+    })
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GitApplyRejection {
+    pub path: String,
+    pub message: String,
+}
+
+#[derive(Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GitApplyResponse {
+    pub clean: Vec<String>,
+    /// The subset of `clean` the three-way apply also staged. The rest were
+    /// applied to the working tree only, leaving the index as it was.
+    pub staged: Vec<String>,
+    /// Applied through a three-way merge that left conflict markers; the
+    /// conflicts are recorded in the index like a regular merge.
+    pub conflicted: Vec<String>,
+    pub rejected: Vec<GitApplyRejection>,
+}
+
+enum ApplyOutcome {
+    Clean,
+    Conflicted,
+    Failed(String),
+}
+
+fn git_apply(repo_path: &str, args: &[&str], patch: &[u8]) -> Result<ApplyOutcome, String> {
+    let mut child = Command::new("git")
+        .arg("-C")
+        .arg(repo_path)
+        .arg("apply")
+        .args(args)
+        .arg("-")
+        // The outcome is read from git's messages, which must not be
+        // translated.
+        .env("LC_ALL", "C")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|error| error.to_string())?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(patch).map_err(|error| error.to_string())?;
+    }
+    let output = child
+        .wait_with_output()
+        .map_err(|error| error.to_string())?;
+    let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+    if stderr.contains("with conflicts") {
+        return Ok(ApplyOutcome::Conflicted);
+    }
+    if output.status.success() {
+        return Ok(ApplyOutcome::Clean);
+    }
+    Ok(ApplyOutcome::Failed(if stderr.is_empty() {
+        "patch does not apply".to_string()
+    } else {
+        stderr
+    }))
+}
+
+/// Applies the changes of the task checked out at `source_path` (against its
+/// `source_base_branch`) onto the worktree at `target_path`, one file at a
+/// time so a file that cannot be applied does not block the others.
+#[tauri::command]
+pub fn apply_task_changes(
+    source_path: String,
+    source_base_branch: String,
+    target_path: String,
+) -> Result<GitApplyResponse, String> {
+    let source = Repository::discover(&source_path).map_err(|error| error.to_string())?;
+    let diff = task_diff(&source, &source_base_branch)?;
+    let mut response = GitApplyResponse::default();
+
+    for (index, delta) in diff.deltas().enumerate() {
+        let path = delta
+            .new_file()
+            .path()
+            .or_else(|| delta.old_file().path())
+            .map(|path| path.to_string_lossy().replace('\\', "/"))
+            .unwrap_or_default();
+        let Some(mut patch) = Patch::from_diff(&diff, index).map_err(|error| error.to_string())?
+        else {
+            continue;
+        };
+        let buffer = patch.to_buf().map_err(|error| error.to_string())?;
+        if buffer.is_empty() {
+            continue;
+        }
+
+        // The three-way fallback needs the index to match the file, which
+        // fails on unstaged edits; a plain apply may still succeed there.
+        let (outcome, staged) = match git_apply(&target_path, &["--3way"], &buffer)? {
+            ApplyOutcome::Failed(_) => (git_apply(&target_path, &[], &buffer)?, false),
+            outcome => (outcome, true),
+        };
+        match outcome {
+            ApplyOutcome::Clean => {
+                if staged {
+                    response.staged.push(path.clone());
+                }
+                response.clean.push(path);
+            }
+            ApplyOutcome::Conflicted => response.conflicted.push(path),
+            ApplyOutcome::Failed(message) => {
+                response.rejected.push(GitApplyRejection { path, message })
+            }
+        }
+    }
+
+    Ok(response)
+}
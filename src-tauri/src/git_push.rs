@@ -0,0 +1,518 @@
+use git2::{BranchType, Repository};
+use serde::{Deserialize, Serialize};
+use std::io::{BufReader, Read};
+use std::process::{Command, Stdio};
+use tauri::Emitter;
+
+#[derive(Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GitPushOptions {
+    /// Record the pushed branch as the local branch's upstream. Defaults to
+    /// true.
+    pub set_upstream: Option<bool>,
+    /// Overwrite the remote branch, but only if it still points where this
+    /// repository last saw it (or at `expected_remote_id` when given).
+    pub force_with_lease: Option<bool>,
+    pub expected_remote_id: Option<String>,
+    /// Name of the branch on the remote; defaults to the local name.
+    pub remote_branch: Option<String>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum GitPushStatus {
+    Pushed,
+    UpToDate,
+    Rejected,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum GitPushRejectionKind {
+    /// The remote has commits the local branch does not.
+    NonFastForward,
+    /// Same, and the remote commits have not been fetched yet.
+    FetchFirst,
+    /// Force-with-lease found the remote branch moved since it was last seen.
+    StaleInfo,
+    /// A hook or branch protection on the server refused the update.
+    RemoteRejected,
+    Other,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GitPushRejection {
+    pub kind: GitPushRejectionKind,
+    pub message: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GitPushResponse {
+    pub remote: String,
+    pub remote_ref: String,
+    pub status: GitPushStatus,
+    pub forced: bool,
+    pub created: bool,
+    /// Upstream of the local branch after the push.
+    pub upstream: Option<String>,
+    pub rejection: Option<GitPushRejection>,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GitPushProgressEvent {
+    pub repo_path: String,
+    pub branch: String,
+    /// e.g. "Counting objects" or "Writing objects".
+    pub stage: String,
+    pub percent: Option<u32>,
+    pub current: Option<u64>,
+    pub total: Option<u64>,
+    pub message: String,
+}
+
+/// Parses a progress line such as `Writing objects:  45% (9/20), 1.2 KiB`.
+fn parse_progress(repo_path: &str, branch: &str, line: &str) -> Option<GitPushProgressEvent> {
+    let text = line.trim().trim_start_matches("remote:").trim();
+    let (stage, rest) = text.split_once(':')?;
+    let rest = rest.trim();
+    let (percent, rest) = rest.split_once('%')?;
+    let percent = percent.trim().parse::<u32>().ok()?;
+    let counts = rest
+        .trim()
+        .strip_prefix('(')
+        .and_then(|value| value.split_once(')'))
+        .and_then(|(counts, _)| counts.split_once('/'))
+        .and_then(|(current, total)| Some((current.parse().ok()?, total.parse().ok()?)));
+    Some(GitPushProgressEvent {
+        repo_path: repo_path.to_string(),
+        branch: branch.to_string(),
+        stage: stage.trim().to_string(),
+        percent: Some(percent),
+        current: counts.map(|(current, _)| current),
+        total: counts.map(|(_, total)| total),
+        message: text.to_string(),
+    })
+}
+
+/// Reads git's stderr, which separates progress updates with `\r`, passing
+/// each progress update to `on_progress` and returning every other line.
+fn forward_progress(
+    stderr: impl Read,
+    repo_path: &str,
+    branch: &str,
+    on_progress: &impl Fn(GitPushProgressEvent),
+) -> Vec<String> {
+    let mut reader = BufReader::new(stderr);
+    let mut messages = Vec::new();
+    let mut line = Vec::new();
+    let mut byte = [0u8; 1];
+    loop {
+        let done = !matches!(reader.read(&mut byte), Ok(1));
+        if !done && byte[0] != b'\r' && byte[0] != b'\n' {
+            line.push(byte[0]);
+            continue;
+        }
+        let text = String::from_utf8_lossy(&line).trim_end().to_string();
+        line.clear();
+        if !text.is_empty() {
+            match parse_progress(repo_path, branch, &text) {
+                Some(event) => on_progress(event),
+                None => {
+                    if messages.last() != Some(&text) {
+                        messages.push(text);
+                    }
+                }
+            }
+        }
+        if done {
+            break;
+        }
+    }
+    messages
+}
+
+fn rejection_kind(summary: &str) -> GitPushRejectionKind {
+    if summary.contains("stale info") {
+        GitPushRejectionKind::StaleInfo
+    } else if summary.contains("fetch first") {
+        GitPushRejectionKind::FetchFirst
+    } else if summary.contains("non-fast-forward") {
+        GitPushRejectionKind::NonFastForward
+    } else if summary.contains("remote rejected") {
+        GitPushRejectionKind::RemoteRejected
+    } else {
+        GitPushRejectionKind::Other
+    }
+}
+
+/// Finds the flag and summary of `remote_ref` in `git push --porcelain`
+/// output, which has one `<flag>\t<from>:<to>\t<summary>` line per ref.
+fn parse_porcelain_ref(stdout: &str, remote_ref: &str) -> Option<(char, String)> {
+    let suffix = format!(":{}", remote_ref);
+    stdout.lines().find_map(|line| {
+        let mut fields = line.splitn(3, '\t');
+        let flag = fields.next()?.chars().next()?;
+        let refs = fields.next()?;
+        let summary = fields.next().unwrap_or_default();
+        refs.ends_with(&suffix).then(|| (flag, summary.to_string()))
+    })
+}
+
+/// Pushes a local branch by running `git push`, so the user's credential
+/// helpers, SSH config and hooks apply as they do in a terminal. Progress is
+/// emitted as `git-push-progress` events; a refused update comes back as a
+/// `rejected` status rather than an error.
+#[tauri::command]
+pub async fn git_push(
+    repo_path: String,
+    branch: Option<String>,
+    remote: Option<String>,
+    options: Option<GitPushOptions>,
+    app: tauri::AppHandle,
+) -> Result<GitPushResponse, String> {
+    // The push waits on the network; keep it off the async runtime's workers.
+    tauri::async_runtime::spawn_blocking(move || {
+        push_branch(
+            &repo_path,
+            branch,
+            remote,
+            options.unwrap_or_default(),
+            |event| {
+                let _ = app.emit("git-push-progress", event);
+            },
+        )
+    })
+    .await
+    .map_err(|error| error.to_string())?
+}
+
+fn push_branch(
+    repo_path: &str,
+    branch: Option<String>,
+    remote: Option<String>,
+    options: GitPushOptions,
+    on_progress: impl Fn(GitPushProgressEvent) + Sync,
+) -> Result<GitPushResponse, String> {
+    let repo = Repository::discover(repo_path).map_err(|error| error.to_string())?;
+    let branch = match branch.map(|value| value.trim().to_string()) {
+        Some(branch) if !branch.is_empty() => branch,
+        _ => {
+            let head = repo.head().map_err(|error| error.to_string())?;
+            if !head.is_branch() {
+                return Err("the worktree is not on a branch".to_string());
+            }
+            head.shorthand().unwrap_or_default().to_string()
+        }
+    };
+    repo.find_branch(&branch, BranchType::Local)
+        .map_err(|_| format!("branch '{}' not found", branch))?;
+    let local_ref = format!("refs/heads/{}", branch);
+
+    // Default to the branch's configured remote, then `origin`.
+    let remote = match remote.map(|value| value.trim().to_string()) {
+        Some(remote) if !remote.is_empty() => remote,
+        _ => repo
+            .branch_upstream_remote(&local_ref)
+            .ok()
+            .and_then(|name| name.as_str().map(str::to_string))
+            .unwrap_or_else(|| "origin".to_string()),
+    };
+    repo.find_remote(&remote)
+        .map_err(|_| format!("remote '{}' not found", remote))?;
+    let remote_branch = options
+        .remote_branch
+        .as_deref()
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+        .unwrap_or(&branch)
+        .to_string();
+    let remote_ref = format!("refs/heads/{}", remote_branch);
+
+    let mut args = vec![
+        "push".to_string(),
+        "--porcelain".to_string(),
+        "--progress".to_string(),
+    ];
+    if options.set_upstream.unwrap_or(true) {
+        args.push("--set-upstream".to_string());
+    }
+    if options.force_with_lease.unwrap_or(false) {
+        args.push(match options.expected_remote_id.as_deref().map(str::trim) {
+            Some(expected) if !expected.is_empty() => {
+                format!("--force-with-lease={}:{}", remote_ref, expected)
+            }
+            _ => format!("--force-with-lease={}", remote_ref),
+        });
+    }
+    args.push(remote.clone());
+    args.push(format!("{}:{}", local_ref, remote_ref));
+
+    let mut child = Command::new("git")
+        .arg("-C")
+        .arg(repo_path)
+        .args(&args)
+        // There is no terminal to answer a credential prompt; fail instead of
+        // hanging when no helper can supply credentials.
+        .env("GIT_TERMINAL_PROMPT", "0")
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|error| error.to_string())?;
+    let stderr = child
+        .stderr
+        .take()
+        .ok_or_else(|| "failed to read git output".to_string())?;
+    let (stdout, status, messages) = std::thread::scope(|scope| {
+        let progress = scope.spawn(|| forward_progress(stderr, repo_path, &branch, &on_progress));
+        let mut stdout = String::new();
+        if let Some(mut out) = child.stdout.take() {
+            out.read_to_string(&mut stdout)
+                .map_err(|error| error.to_string())?;
+        }
+        let status = child.wait().map_err(|error| error.to_string())?;
+        let messages = progress.join().unwrap_or_default();
+        Ok::<_, String>((stdout, status, messages))
+    })?;
+
+    let Some((flag, summary)) = parse_porcelain_ref(&stdout, &remote_ref) else {
+        let message = messages.join("\n");
+        return Err(if message.is_empty() {
+            "git push failed".to_string()
+        } else {
+            message
+        });
+    };
+
+    let (push_status, rejection) = match flag {
+        '=' => (GitPushStatus::UpToDate, None),
+        '!' => {
+            // Hook and branch-protection output arrives as `remote:` lines.
+            let mut message = summary.clone();
+            for line in messages.iter().filter(|line| line.starts_with("remote:")) {
+                message.push('\n');
+                message.push_str(line);
+            }
+            (
+                GitPushStatus::Rejected,
+                Some(GitPushRejection {
+                    kind: rejection_kind(&summary),
+                    message,
+                }),
+            )
+        }
+        _ if status.success() => (GitPushStatus::Pushed, None),
+        _ => return Err(messages.join("\n")),
+    };
+
+    let repo = Repository::discover(repo_path).map_err(|error| error.to_string())?;
+    let upstream = repo
+        .find_branch(&branch, BranchType::Local)
+        .ok()
+        .and_then(|local| local.upstream().ok())
+        .and_then(|upstream| upstream.name().ok().flatten().map(str::to_string));
+
+    Ok(GitPushResponse {
+        remote,
+        remote_ref,
+        status: push_status,
+        forced: flag == '+',
+        created: flag == '*',
+        upstream,
+        rejection,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::{Path, PathBuf};
+
+    /// A scratch directory, deleted on drop so test runs leave nothing behind.
+    struct TempDir(PathBuf);
+
+    impl std::ops::Deref for TempDir {
+        type Target = Path;
+
+        fn deref(&self) -> &Path {
+            &self.0
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn temp_dir(name: &str) -> TempDir {
+        static COUNTER: std::sync::atomic::AtomicUsize = std::sync::atomic::AtomicUsize::new(0);
+        let dir = std::env::temp_dir().join(format!(
+            "prompter-push-test-{}-{}-{}",
+            std::process::id(),
+            COUNTER.fetch_add(1, std::sync::atomic::Ordering::Relaxed),
+            name
+        ));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        TempDir(dir)
+    }
+
+    fn git(dir: &Path, args: &[&str]) -> String {
+        let output = Command::new("git")
+            .arg("-C")
+            .arg(dir)
+            .args(["-c", "user.name=Test", "-c", "user.email=test@example.com"])
+            .args(args)
+            .output()
+            .unwrap();
+        assert!(
+            output.status.success(),
+            "git {:?} failed: {}",
+            args,
+            String::from_utf8_lossy(&output.stderr)
+        );
+        String::from_utf8_lossy(&output.stdout).trim().to_string()
+    }
+
+    fn commit(dir: &Path, content: &str) {
+        std::fs::write(dir.join("file.txt"), content).unwrap();
+        git(dir, &["add", "file.txt"]);
+        git(dir, &["commit", "-q", "-m", content]);
+    }
+
+    /// A repository on `main` with one commit and a bare `origin`.
+    fn repo_with_remote() -> (TempDir, TempDir) {
+        let remote = temp_dir("remote.git");
+        git(&remote, &["init", "-q", "--bare", "-b", "main"]);
+        let local = temp_dir("local");
+        git(&local, &["init", "-q", "-b", "main"]);
+        git(
+            &local,
+            &["remote", "add", "origin", remote.to_str().unwrap()],
+        );
+        commit(&local, "one");
+        (local, remote)
+    }
+
+    /// Another clone of `remote` that pushes a commit to `main`.
+    fn push_from_elsewhere(remote: &Path) {
+        let other = temp_dir("other");
+        git(
+            &other,
+            &["clone", "-q", "-b", "main", remote.to_str().unwrap(), "."],
+        );
+        commit(&other, "elsewhere");
+        git(&other, &["push", "-q", "origin", "main"]);
+    }
+
+    fn push(local: &Path, options: GitPushOptions) -> GitPushResponse {
+        push_branch(local.to_str().unwrap(), None, None, options, |_| {}).unwrap()
+    }
+
+    #[test]
+    fn pushes_a_new_branch_and_sets_upstream() {
+        let (local, remote) = repo_with_remote();
+        let response = push(&local, GitPushOptions::default());
+        assert!(response.status == GitPushStatus::Pushed);
+        assert!(response.created);
+        assert_eq!(response.remote, "origin");
+        assert_eq!(response.remote_ref, "refs/heads/main");
+        assert_eq!(response.upstream.as_deref(), Some("origin/main"));
+        assert_eq!(
+            git(&remote, &["rev-parse", "main"]),
+            git(&local, &["rev-parse", "main"])
+        );
+    }
+
+    #[test]
+    fn reports_an_up_to_date_branch() {
+        let (local, _remote) = repo_with_remote();
+        push(&local, GitPushOptions::default());
+        let response = push(&local, GitPushOptions::default());
+        assert!(response.status == GitPushStatus::UpToDate);
+        assert!(!response.created);
+    }
+
+    #[test]
+    fn rejects_a_non_fast_forward_push() {
+        let (local, remote) = repo_with_remote();
+        push(&local, GitPushOptions::default());
+        push_from_elsewhere(&remote);
+        commit(&local, "two");
+
+        let response = push(&local, GitPushOptions::default());
+        assert!(response.status == GitPushStatus::Rejected);
+        let rejection = response.rejection.unwrap();
+        assert!(rejection.kind == GitPushRejectionKind::FetchFirst);
+
+        git(&local, &["fetch", "-q", "origin"]);
+        let response = push(&local, GitPushOptions::default());
+        let rejection = response.rejection.unwrap();
+        assert!(rejection.kind == GitPushRejectionKind::NonFastForward);
+    }
+
+    #[test]
+    fn force_with_lease_refuses_a_stale_lease() {
+        let (local, remote) = repo_with_remote();
+        push(&local, GitPushOptions::default());
+        push_from_elsewhere(&remote);
+        git(&local, &["commit", "-q", "--amend", "-m", "rewritten"]);
+        let force = || GitPushOptions {
+            force_with_lease: Some(true),
+            ..GitPushOptions::default()
+        };
+
+        // origin/main still records the commit before the other push.
+        let response = push(&local, force());
+        assert!(response.status == GitPushStatus::Rejected);
+        assert!(response.rejection.unwrap().kind == GitPushRejectionKind::StaleInfo);
+
+        git(&local, &["fetch", "-q", "origin"]);
+        let response = push(&local, force());
+        assert!(response.status == GitPushStatus::Pushed);
+        assert!(response.forced);
+    }
+
+    #[test]
+    fn parses_progress_lines() {
+        let event =
+            parse_progress("/repo", "main", "Writing objects:  45% (9/20), 1.2 KiB").unwrap();
+        assert_eq!(event.stage, "Writing objects");
+        assert_eq!(event.percent, Some(45));
+        assert_eq!(event.current, Some(9));
+        assert_eq!(event.total, Some(20));
+
+        let event = parse_progress(
+            "/repo",
+            "main",
+            "remote: Resolving deltas: 100% (3/3), done.",
+        )
+        .unwrap();
+        assert_eq!(event.stage, "Resolving deltas");
+        assert_eq!(event.percent, Some(100));
+
+        assert!(parse_progress("/repo", "main", "To /tmp/remote.git").is_none());
+        assert!(parse_progress("/repo", "main", "remote: error: denied: 5% left").is_none());
+    }
+
+    #[test]
+    fn parses_porcelain_ref_lines() {
+        let stdout = "To /tmp/remote.git\n\
+                      =\trefs/heads/other:refs/heads/other\t[up to date]\n\
+                      !\trefs/heads/main:refs/heads/main\t[rejected] (stale info)\n\
+                      Done\n";
+        assert_eq!(
+            parse_porcelain_ref(stdout, "refs/heads/main"),
+            Some(('!', "[rejected] (stale info)".to_string()))
+        );
+        assert_eq!(
+            parse_porcelain_ref(stdout, "refs/heads/other"),
+            Some(('=', "[up to date]".to_string()))
+        );
+        assert_eq!(parse_porcelain_ref(stdout, "refs/heads/missing"), None);
+    }
+}
@@ -0,0 +1,174 @@
+use crate::git_merge::{collect_conflicts, ensure_clean_checkout, GitMergeConflict};
+use crate::{get_git_diff_stats, resolve_base_commit, GitDiffStatsResponse};
+use git2::{ErrorCode, Rebase, Repository, Signature};
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GitRebaseAction {
+    #[default]
+    Start,
+    Continue,
+    Abort,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum GitRebaseStatus {
+    UpToDate,
+    Completed,
+    /// Stopped on a commit that does not apply cleanly; resolve the conflicts
+    /// and continue, or abort.
+    Conflicts,
+    Aborted,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GitRebaseResponse {
+    pub status: GitRebaseStatus,
+    /// Commits replayed so far and in total.
+    pub applied: usize,
+    pub total: usize,
+    /// The original commit being replayed when the rebase stopped.
+    pub stopped_at: Option<String>,
+    pub conflicts: Vec<GitMergeConflict>,
+    /// Diff stats against the base after a completed or aborted rebase.
+    pub stats: Option<GitDiffStatsResponse>,
+}
+
+/// Replays the remaining operations, committing each one, until the rebase
+/// finishes or a commit stops on conflicts.
+fn run_rebase(
+    repo: &Repository,
+    rebase: &mut Rebase,
+    signature: &Signature,
+    path: &str,
+    base_branch: &str,
+) -> Result<GitRebaseResponse, String> {
+    let total = rebase.len();
+    loop {
+        let mut index = repo.index().map_err(|error| error.to_string())?;
+        // Conflicts may have been resolved on disk since the index was loaded.
+        index.read(false).map_err(|error| error.to_string())?;
+        if index.has_conflicts() {
+            let current = rebase.operation_current().unwrap_or(0);
+            let stopped_at = rebase
+                .nth(current)
+                .map(|operation| operation.id().to_string());
+            return Ok(GitRebaseResponse {
+                status: GitRebaseStatus::Conflicts,
+                applied: current,
+                total,
+                stopped_at,
+                conflicts: collect_conflicts(&index)?,
+                stats: None,
+            });
+        }
+
+        if rebase.operation_current().is_some() {
+            // A commit whose changes are already on the base becomes empty
+            // and is dropped, like `git rebase` does.
+            if let Err(error) = rebase.commit(None, signature, None) {
+                if error.code() != ErrorCode::Applied {
+                    return Err(error.to_string());
+                }
+            }
+        }
+
+        match rebase.next() {
+            Some(operation) => {
+                operation.map_err(|error| error.to_string())?;
+            }
+            None => break,
+        }
+    }
+
+    rebase
+        .finish(Some(signature))
+        .map_err(|error| error.to_string())?;
+    Ok(GitRebaseResponse {
+        status: GitRebaseStatus::Completed,
+        applied: total,
+        total,
+        stopped_at: None,
+        conflicts: Vec::new(),
+        stats: Some(get_git_diff_stats(
+            path.to_string(),
+            base_branch.to_string(),
+            None,
+        )?),
+    })
+}
+
+#[tauri::command]
+pub fn rebase_task_branch(
+    path: String,
+    base_branch: String,
+    action: Option<GitRebaseAction>,
+) -> Result<GitRebaseResponse, String> {
+    let repo = Repository::discover(&path).map_err(|error| error.to_string())?;
+
+    // Only replaying commits needs an identity; aborting works without one.
+    match action.unwrap_or_default() {
+        GitRebaseAction::Start => {
+            let signature = repo.signature().map_err(|error| error.to_string())?;
+            if repo.open_rebase(None).is_ok() {
+                return Err("a rebase is already in progress; continue or abort it".to_string());
+            }
+            ensure_clean_checkout(&repo)?;
+            let head = repo.head().map_err(|error| error.to_string())?;
+            if !head.is_branch() {
+                return Err("the worktree is not on a branch".to_string());
+            }
+            let head_commit = head.peel_to_commit().map_err(|error| error.to_string())?;
+            let base = resolve_base_commit(&repo, &base_branch)?;
+            let merge_base = repo
+                .merge_base(head_commit.id(), base.id())
+                .map_err(|error| error.to_string())?;
+            if merge_base == base.id() {
+                return Ok(GitRebaseResponse {
+                    status: GitRebaseStatus::UpToDate,
+                    applied: 0,
+                    total: 0,
+                    stopped_at: None,
+                    conflicts: Vec::new(),
+                    stats: Some(get_git_diff_stats(path, base_branch, None)?),
+                });
+            }
+
+            let branch = repo
+                .reference_to_annotated_commit(&head)
+                .map_err(|error| error.to_string())?;
+            let upstream = repo
+                .find_annotated_commit(base.id())
+                .map_err(|error| error.to_string())?;
+            let mut rebase = repo
+                .rebase(Some(&branch), Some(&upstream), None, None)
+                .map_err(|error| error.to_string())?;
+            run_rebase(&repo, &mut rebase, &signature, &path, &base_branch)
+        }
+        GitRebaseAction::Continue => {
+            let signature = repo.signature().map_err(|error| error.to_string())?;
+            let mut rebase = repo
+                .open_rebase(None)
+                .map_err(|_| "no rebase is in progress".to_string())?;
+            run_rebase(&repo, &mut rebase, &signature, &path, &base_branch)
+        }
+        GitRebaseAction::Abort => {
+            let mut rebase = repo
+                .open_rebase(None)
+                .map_err(|_| "no rebase is in progress".to_string())?;
+            let total = rebase.len();
+            rebase.abort().map_err(|error| error.to_string())?;
+            Ok(GitRebaseResponse {
+                status: GitRebaseStatus::Aborted,
+                applied: 0,
+                total,
+                stopped_at: None,
+                conflicts: Vec::new(),
+                stats: Some(get_git_diff_stats(path, base_branch, None)?),
+            })
+        }
+    }
+}
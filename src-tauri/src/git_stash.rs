@@ -0,0 +1,230 @@
+use crate::git_diff::{self, GitDiffFile, GitDiffRequestOptions};
+use crate::git_index::open_repo;
+use crate::git_merge::{collect_conflicts, GitMergeConflict};
+use git2::{Diff, ErrorCode, Oid, Repository, StashApplyOptions, StashApplyProgress, StashFlags};
+use serde::Serialize;
+use std::cell::Cell;
+use std::path::Path;
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GitStashEntry {
+    /// Position in the stash list; `0` is the most recent (`stash@{0}`).
+    pub index: usize,
+    pub id: String,
+    pub message: String,
+    /// Creation time in milliseconds since the Unix epoch.
+    pub time: i64,
+    /// Whether untracked files were stashed along with the changes.
+    pub includes_untracked: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub files: Option<Vec<GitDiffFile>>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GitStashApplyResponse {
+    /// Whether the stash was removed from the list after applying.
+    pub dropped: bool,
+    /// Paths left conflicted in the index by the apply.
+    pub conflicts: Vec<GitMergeConflict>,
+}
+
+fn stash_ids(repo: &mut Repository) -> Result<Vec<(usize, String, Oid)>, String> {
+    let mut stashes = Vec::new();
+    repo.stash_foreach(|index, message, id| {
+        stashes.push((index, message.to_string(), *id));
+        true
+    })
+    .map_err(|error| error.to_string())?;
+    Ok(stashes)
+}
+
+/// Resolves `index` to a stash, failing if `id` is given and no longer
+/// matches, so a stale list cannot act on the wrong entry.
+fn checked_index(repo: &mut Repository, index: usize, id: Option<&str>) -> Result<usize, String> {
+    let stashes = stash_ids(repo)?;
+    let (_, _, stash_id) = stashes
+        .iter()
+        .find(|(position, _, _)| *position == index)
+        .ok_or_else(|| format!("stash@{{{}}} does not exist", index))?;
+    if let Some(id) = id.map(str::trim).filter(|id| !id.is_empty()) {
+        if stash_id.to_string() != id {
+            return Err(format!(
+                "stash@{{{}}} has changed since it was listed; refresh the stash list",
+                index
+            ));
+        }
+    }
+    Ok(index)
+}
+
+/// The changes a stash commit records: its tracked changes against the
+/// commit it was made on, plus any untracked files from its third parent.
+fn stash_diff<'repo>(
+    repo: &'repo Repository,
+    id: Oid,
+    options: &GitDiffRequestOptions,
+) -> Result<Diff<'repo>, String> {
+    let stash = repo.find_commit(id).map_err(|error| error.to_string())?;
+    let base = stash.parent(0).map_err(|error| error.to_string())?;
+    let base_tree = base.tree().map_err(|error| error.to_string())?;
+    let stash_tree = stash.tree().map_err(|error| error.to_string())?;
+
+    let mut diff_opts = git_diff::workdir_diff_options(options);
+    let mut diff = repo
+        .diff_tree_to_tree(Some(&base_tree), Some(&stash_tree), Some(&mut diff_opts))
+        .map_err(|error| error.to_string())?;
+    if let Ok(untracked) = stash.parent(2) {
+        let untracked_tree = untracked.tree().map_err(|error| error.to_string())?;
+        let untracked_diff = repo
+            .diff_tree_to_tree(None, Some(&untracked_tree), Some(&mut diff_opts))
+            .map_err(|error| error.to_string())?;
+        diff.merge(&untracked_diff)
+            .map_err(|error| error.to_string())?;
+    }
+    git_diff::find_renames(&mut diff, options)?;
+    Ok(diff)
+}
+
+/// Stashes the working tree and index changes, optionally with untracked
+/// files, and returns the new `stash@{0}` entry.
+#[tauri::command]
+pub fn git_stash_save(
+    path: String,
+    message: Option<String>,
+    include_untracked: Option<bool>,
+    keep_index: Option<bool>,
+) -> Result<GitStashEntry, String> {
+    let mut repo = open_repo(&path)?;
+    let signature = repo.signature().map_err(|error| error.to_string())?;
+    let mut flags = StashFlags::DEFAULT;
+    if include_untracked.unwrap_or(false) {
+        flags |= StashFlags::INCLUDE_UNTRACKED;
+    }
+    if keep_index.unwrap_or(false) {
+        flags |= StashFlags::KEEP_INDEX;
+    }
+    let message = message
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty());
+    let id = repo
+        .stash_save2(&signature, message.as_deref(), Some(flags))
+        .map_err(|error| match error.code() {
+            ErrorCode::NotFound => "no local changes to stash".to_string(),
+            _ => error.to_string(),
+        })?;
+
+    let (_, message, _) = stash_ids(&mut repo)?
+        .into_iter()
+        .find(|(_, _, stash_id)| *stash_id == id)
+        .ok_or_else(|| "stash was not recorded".to_string())?;
+    let commit = repo.find_commit(id).map_err(|error| error.to_string())?;
+    Ok(GitStashEntry {
+        index: 0,
+        id: id.to_string(),
+        message,
+        time: commit.time().seconds() * 1000,
+        includes_untracked: commit.parent_count() > 2,
+        files: None,
+    })
+}
+
+/// Lists stashes, newest first. With `include_diff`, each entry carries its
+/// changes in the same shape as the working-tree diff.
+#[tauri::command]
+pub fn list_git_stashes(
+    path: String,
+    include_diff: Option<bool>,
+    options: Option<GitDiffRequestOptions>,
+) -> Result<Vec<GitStashEntry>, String> {
+    let options = options.unwrap_or_default();
+    let mut repo = open_repo(&path)?;
+    let stashes = stash_ids(&mut repo)?;
+    let root = repo
+        .workdir()
+        .unwrap_or_else(|| Path::new(""))
+        .to_path_buf();
+
+    let mut entries = Vec::new();
+    for (index, message, id) in stashes {
+        let commit = repo.find_commit(id).map_err(|error| error.to_string())?;
+        let files = if include_diff.unwrap_or(false) {
+            let diff = stash_diff(&repo, id, &options)?;
+            Some(git_diff::collect_tree_diff_files(
+                &repo, &root, &diff, &options,
+            )?)
+        } else {
+            None
+        };
+        entries.push(GitStashEntry {
+            index,
+            id: id.to_string(),
+            message,
+            time: commit.time().seconds() * 1000,
+            includes_untracked: commit.parent_count() > 2,
+            files,
+        });
+    }
+    Ok(entries)
+}
+
+/// Applies a stash onto the working tree, and with `pop` drops it once it
+/// applied. `reinstate_index` restores what was staged as staged.
+#[tauri::command]
+pub fn apply_git_stash(
+    path: String,
+    index: usize,
+    id: Option<String>,
+    pop: Option<bool>,
+    reinstate_index: Option<bool>,
+) -> Result<GitStashApplyResponse, String> {
+    let mut repo = open_repo(&path)?;
+    let index = checked_index(&mut repo, index, id.as_deref())?;
+    let reinstate_index = reinstate_index.unwrap_or(false);
+    // The stage libgit2 reached tells which part of the stash conflicted.
+    let stage = Cell::new(StashApplyProgress::None);
+    let mut apply_opts = StashApplyOptions::new();
+    apply_opts.progress_cb(|progress| {
+        stage.set(progress);
+        true
+    });
+    if reinstate_index {
+        apply_opts.reinstantiate_index();
+    }
+
+    repo.stash_apply(index, Some(&mut apply_opts))
+        .map_err(|error| match (error.code(), stage.get()) {
+            (ErrorCode::Conflict, StashApplyProgress::AnalyzeIndex) if reinstate_index => {
+                "the stash's staged changes conflict; apply without restoring the index".to_string()
+            }
+            (ErrorCode::Conflict, StashApplyProgress::CheckoutUntracked) => {
+                "untracked files in the stash already exist in the working tree".to_string()
+            }
+            (ErrorCode::Conflict, _) => {
+                "local changes would be overwritten by the stash; commit or stash them first"
+                    .to_string()
+            }
+            _ => error.to_string(),
+        })?;
+
+    // A stash that applies with conflicts stays in the list, like `git stash
+    // pop` does, so nothing is lost while the conflicts are resolved.
+    let conflicts = {
+        let mut repo_index = repo.index().map_err(|error| error.to_string())?;
+        repo_index.read(false).map_err(|error| error.to_string())?;
+        collect_conflicts(&repo_index)?
+    };
+    let dropped = pop.unwrap_or(false) && conflicts.is_empty();
+    if dropped {
+        repo.stash_drop(index).map_err(|error| error.to_string())?;
+    }
+    Ok(GitStashApplyResponse { dropped, conflicts })
+}
+
+#[tauri::command]
+pub fn drop_git_stash(path: String, index: usize, id: Option<String>) -> Result<(), String> {
+    let mut repo = open_repo(&path)?;
+    let index = checked_index(&mut repo, index, id.as_deref())?;
+    repo.stash_drop(index).map_err(|error| error.to_string())
+}
@@ -0,0 +1,97 @@
+use crate::git_diff::{self, GitDiffFile, GitDiffRequestOptions};
+use git2::{DiffDelta, FileMode, Oid, Repository, SubmoduleIgnore, SubmoduleStatus};
+use serde::Serialize;
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GitSubmoduleChange {
+    pub url: Option<String>,
+    /// Commits the superproject records on each side of the diff; `None`
+    /// where the submodule does not exist.
+    pub old_commit: Option<String>,
+    pub new_commit: Option<String>,
+    /// The submodule checkout has staged or unstaged edits of its own.
+    pub has_modified_content: bool,
+    pub has_untracked_content: bool,
+    /// The submodule is registered but not checked out.
+    pub uninitialized: bool,
+    /// The submodule's own working-tree changes, with paths relative to the
+    /// submodule, when `recurseSubmodules` was requested.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub files: Option<Vec<GitDiffFile>>,
+}
+
+fn commit_id(id: Oid) -> Option<String> {
+    (!id.is_zero()).then(|| id.to_string())
+}
+
+/// Whether either side of the delta is a gitlink (a submodule commit).
+pub fn is_submodule_delta(delta: &DiffDelta) -> bool {
+    delta.old_file().mode() == FileMode::Commit || delta.new_file().mode() == FileMode::Commit
+}
+
+/// Diffs the submodule checkout against its own HEAD, recursing further
+/// into nested submodules with the same options.
+fn submodule_files(
+    repo: &Repository,
+    options: &GitDiffRequestOptions,
+) -> Result<Vec<GitDiffFile>, String> {
+    let Some(root) = repo.workdir() else {
+        return Ok(Vec::new());
+    };
+    let head_tree = repo.head().ok().and_then(|head| head.peel_to_tree().ok());
+    let mut diff_opts = git_diff::workdir_diff_options(options);
+    let diff = git_diff::tree_workdir_diff(repo, head_tree.as_ref(), options, &mut diff_opts)?;
+    let (files, _) = git_diff::collect_diff_files(repo, root, &diff, options)?;
+    Ok(files)
+}
+
+/// Describes a submodule delta. Submodules that are gone from the working
+/// tree are described from the commits alone.
+pub fn submodule_change(
+    repo: &Repository,
+    delta: &DiffDelta,
+    path: &str,
+    options: &GitDiffRequestOptions,
+) -> Result<GitSubmoduleChange, String> {
+    let old_commit = (delta.old_file().mode() == FileMode::Commit)
+        .then(|| commit_id(delta.old_file().id()))
+        .flatten();
+    let new_commit = (delta.new_file().mode() == FileMode::Commit)
+        .then(|| commit_id(delta.new_file().id()))
+        .flatten();
+    let mut change = GitSubmoduleChange {
+        url: None,
+        old_commit,
+        new_commit,
+        has_modified_content: false,
+        has_untracked_content: false,
+        uninitialized: false,
+        files: None,
+    };
+
+    let Ok(submodule) = repo.find_submodule(path) else {
+        return Ok(change);
+    };
+    change.url = submodule.url().map(str::to_string);
+    let name = submodule.name().unwrap_or(path).to_string();
+    if let Ok(status) = repo.submodule_status(&name, SubmoduleIgnore::None) {
+        change.has_modified_content =
+            status.is_wd_wd_modified() || status.contains(SubmoduleStatus::WD_INDEX_MODIFIED);
+        change.has_untracked_content = status.is_wd_untracked();
+        change.uninitialized = status.is_wd_uninitialized();
+    }
+
+    if options.recurse_submodules.unwrap_or(false) && !change.uninitialized {
+        if let Ok(sub_repo) = submodule.open() {
+            // The superproject's pathspecs are relative to its own root.
+            let nested_options = GitDiffRequestOptions {
+                include_paths: None,
+                exclude_paths: None,
+                ..options.clone()
+            };
+            change.files = Some(submodule_files(&sub_repo, &nested_options)?);
+        }
+    }
+    Ok(change)
+}
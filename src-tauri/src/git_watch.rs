@@ -0,0 +1,271 @@
+use git2::Repository;
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use serde::Serialize;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::sync::{Arc, Mutex, Weak};
+use std::time::Duration;
+use tauri::{Emitter, State};
+
+/// Quiet period after the last filesystem event before changes are reported.
+const DEBOUNCE_INTERVAL: Duration = Duration::from_millis(300);
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GitStatusChangedEvent {
+    pub repo: String,
+    /// Changed paths relative to `repo`, sorted and deduplicated.
+    pub paths: Vec<String>,
+}
+
+struct RepoWatch {
+    // Dropping the watcher closes its event channel, which ends the debounce
+    // thread. That thread only holds a weak reference, for registering new
+    // directories.
+    _watcher: Arc<Mutex<RecommendedWatcher>>,
+    subscribers: usize,
+}
+
+#[derive(Default)]
+pub struct GitWatchState {
+    watches: Mutex<HashMap<PathBuf, RepoWatch>>,
+}
+
+fn watch_root(path: &str) -> Result<PathBuf, String> {
+    let repo = Repository::discover(path).map_err(|error| error.to_string())?;
+    let root = repo
+        .workdir()
+        .ok_or_else(|| "repository has no working directory".to_string())?;
+    root.canonicalize().map_err(|error| error.to_string())
+}
+
+/// Canonicalizes the longest existing ancestor of `path` and appends the
+/// rest, so a path whose worktree is already gone still lines up with the
+/// canonical roots watches are keyed by.
+fn canonical_path(path: &Path) -> PathBuf {
+    let mut existing = path;
+    let mut rest = Vec::new();
+    loop {
+        if let Ok(canonical) = existing.canonicalize() {
+            return rest
+                .iter()
+                .rev()
+                .fold(canonical, |path, part| path.join(part));
+        }
+        match (existing.parent(), existing.file_name()) {
+            (Some(parent), Some(name)) => {
+                rest.push(name.to_os_string());
+                existing = parent;
+            }
+            _ => return path.to_path_buf(),
+        }
+    }
+}
+
+/// Registers `dir` and every directory below it that the repository does not
+/// ignore, one non-recursive watch each, so `node_modules`, `target` and the
+/// like never cost a watch. `.git` is skipped too; its events are dropped
+/// anyway.
+fn watch_tree(
+    watcher: &mut RecommendedWatcher,
+    repo: &Repository,
+    root: &Path,
+    dir: &Path,
+    watched: &mut HashSet<PathBuf>,
+) -> Result<(), String> {
+    let mut pending = vec![dir.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        let Ok(relative) = dir.strip_prefix(root) else {
+            continue;
+        };
+        if relative.components().any(|part| part.as_os_str() == ".git") {
+            continue;
+        }
+        // The trailing slash marks the path as a directory for `target/`-style
+        // patterns.
+        let relative = format!("{}/", relative.to_string_lossy().replace('\\', "/"));
+        if relative != "/" && repo.is_path_ignored(&relative).unwrap_or(false) {
+            continue;
+        }
+        if !watched.insert(dir.clone()) {
+            continue;
+        }
+        watcher
+            .watch(&dir, RecursiveMode::NonRecursive)
+            .map_err(|error| error.to_string())?;
+        let Ok(entries) = std::fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            if entry.file_type().is_ok_and(|kind| kind.is_dir()) {
+                pending.push(entry.path());
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Watches directories that appeared in `batch` and forgets the ones that
+/// disappeared, so the watch set follows the tree.
+fn update_watches(
+    watcher: &Weak<Mutex<RecommendedWatcher>>,
+    root: &Path,
+    batch: &BTreeSet<PathBuf>,
+    watched: &mut HashSet<PathBuf>,
+) {
+    let Some(watcher) = watcher.upgrade() else {
+        return;
+    };
+    let Ok(mut watcher) = watcher.lock() else {
+        return;
+    };
+    let Ok(repo) = Repository::open(root) else {
+        return;
+    };
+    for path in batch {
+        match std::fs::symlink_metadata(path) {
+            Ok(metadata) if metadata.is_dir() && !watched.contains(path) => {
+                let _ = watch_tree(&mut watcher, &repo, root, path, watched);
+            }
+            Ok(_) => {}
+            Err(_) => watched.retain(|dir| !dir.starts_with(path)),
+        }
+    }
+}
+
+/// Drops `.git` internals and paths the repository ignores, returning the
+/// rest relative to `root`.
+fn relevant_paths(root: &Path, paths: BTreeSet<PathBuf>) -> Vec<String> {
+    let repo = Repository::open(root).ok();
+    paths
+        .into_iter()
+        .filter_map(|path| {
+            let relative = path.strip_prefix(root).ok()?.to_path_buf();
+            if relative.as_os_str().is_empty()
+                || relative.components().any(|part| part.as_os_str() == ".git")
+            {
+                return None;
+            }
+            if let Some(repo) = &repo {
+                if repo.status_should_ignore(&relative).unwrap_or(false) {
+                    return None;
+                }
+            }
+            Some(relative.to_string_lossy().replace('\\', "/"))
+        })
+        .collect()
+}
+
+/// Collects event paths until nothing has arrived for `DEBOUNCE_INTERVAL`,
+/// then emits one `git-status-changed` event for the batch.
+fn debounce_events(
+    app: tauri::AppHandle,
+    root: PathBuf,
+    events: Receiver<Vec<PathBuf>>,
+    watcher: Weak<Mutex<RecommendedWatcher>>,
+    mut watched: HashSet<PathBuf>,
+) {
+    let repo = root.to_string_lossy().to_string();
+    while let Ok(first) = events.recv() {
+        let mut batch: BTreeSet<PathBuf> = first.into_iter().collect();
+        let disconnected = loop {
+            match events.recv_timeout(DEBOUNCE_INTERVAL) {
+                Ok(paths) => batch.extend(paths),
+                Err(RecvTimeoutError::Timeout) => break false,
+                Err(RecvTimeoutError::Disconnected) => break true,
+            }
+        };
+        if !disconnected {
+            update_watches(&watcher, &root, &batch, &mut watched);
+        }
+        let paths = relevant_paths(&root, batch);
+        if !paths.is_empty() && !disconnected {
+            let _ = app.emit(
+                "git-status-changed",
+                GitStatusChangedEvent {
+                    repo: repo.clone(),
+                    paths,
+                },
+            );
+        }
+        if disconnected {
+            break;
+        }
+    }
+}
+
+/// Starts watching the repository containing `path` (or adds a subscriber
+/// to an existing watch) and returns the watched working-tree root.
+#[tauri::command]
+pub fn watch_git_status(
+    path: String,
+    state: State<GitWatchState>,
+    app: tauri::AppHandle,
+) -> Result<String, String> {
+    let root = watch_root(&path)?;
+    let mut watches = state
+        .watches
+        .lock()
+        .map_err(|_| "git watch state poisoned".to_string())?;
+    if let Some(watch) = watches.get_mut(&root) {
+        watch.subscribers += 1;
+        return Ok(root.to_string_lossy().to_string());
+    }
+
+    let (sender, receiver) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(move |result: notify::Result<notify::Event>| {
+        if let Ok(event) = result {
+            // Reads and opens do not change anything git cares about.
+            if !matches!(event.kind, EventKind::Access(_)) {
+                let _ = sender.send(event.paths);
+            }
+        }
+    })
+    .map_err(|error| error.to_string())?;
+    let repo = Repository::open(&root).map_err(|error| error.to_string())?;
+    let mut watched = HashSet::new();
+    watch_tree(&mut watcher, &repo, &root, &root, &mut watched)?;
+
+    let watcher = Arc::new(Mutex::new(watcher));
+    let thread_watcher = Arc::downgrade(&watcher);
+    let thread_root = root.clone();
+    std::thread::spawn(move || {
+        debounce_events(app, thread_root, receiver, thread_watcher, watched)
+    });
+    watches.insert(
+        root.clone(),
+        RepoWatch {
+            _watcher: watcher,
+            subscribers: 1,
+        },
+    );
+    Ok(root.to_string_lossy().to_string())
+}
+
+/// Removes one subscriber; the watch stops when the last one is gone.
+#[tauri::command]
+pub fn unwatch_git_status(path: String, state: State<GitWatchState>) -> Result<(), String> {
+    // The worktree may already be gone, so fall back to the canonical form of
+    // the path as given and the watched root that contains it.
+    let key = watch_root(&path).unwrap_or_else(|_| canonical_path(Path::new(&path)));
+    let mut watches = state
+        .watches
+        .lock()
+        .map_err(|_| "git watch state poisoned".to_string())?;
+    let Some(root) = watches
+        .keys()
+        .filter(|root| key.starts_with(root))
+        .max_by_key(|root| root.components().count())
+        .cloned()
+    else {
+        return Ok(());
+    };
+    if let Some(watch) = watches.get_mut(&root) {
+        watch.subscribers = watch.subscribers.saturating_sub(1);
+        if watch.subscribers == 0 {
+            watches.remove(&root);
+        }
+    }
+    Ok(())
+}
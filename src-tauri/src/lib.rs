@@ -1,14 +1,42 @@
-use git2::{Delta, DiffOptions, ErrorCode, Oid, Repository};
+mod git_binary;
+mod git_branch;
+mod git_commit;
+mod git_conflict;
+mod git_diff;
+mod git_discard;
+mod git_history;
+mod git_index;
+mod git_intraline;
+mod git_log;
+mod git_merge;
+mod git_patch;
+mod git_push;
+mod git_rebase;
+mod git_stash;
+mod git_submodule;
+mod git_watch;
+mod pty_limits;
+mod transcript;
+
+use git2::{ErrorCode, Repository};
+use git_diff::{
+    GitDiffFile, GitDiffRequestOptions, GitDiffResponse, GitDiffSummaryFile, GitDiffSummaryResponse,
+};
 use portable_pty::{native_pty_system, CommandBuilder, PtySize};
+use pty_limits::PtyResourceLimits;
 use serde::Serialize;
 use std::{
     collections::HashMap,
     io::{Read, Write},
     path::{Path, PathBuf},
     process::{Child, Command, Stdio},
-    sync::Mutex,
+    sync::{Arc, Mutex},
 };
 use tauri::{Emitter, Manager, State, WebviewUrl, WebviewWindowBuilder};
+use tauri_plugin_dialog::DialogExt;
+
+/// Raw output kept per terminal session for transcript exports.
+const SCROLLBACK_LIMIT_BYTES: usize = 4 * 1024 * 1024;
 
 #[derive(Clone, Serialize)]
 struct TerminalOutput {
@@ -16,29 +44,14 @@ struct TerminalOutput {
     data: String,
 }
 
-#[derive(Serialize)]
-#[serde(rename_all = "camelCase")]
-struct GitDiffFile {
-    path: String,
-    status: String,
-    old_content: String,
-    new_content: String,
-    is_binary: bool,
-}
-
-#[derive(Serialize)]
-#[serde(rename_all = "camelCase")]
-struct GitDiffResponse {
-    root: String,
-    files: Vec<GitDiffFile>,
-}
-
 #[derive(Serialize)]
 #[serde(rename_all = "camelCase")]
 struct GitDiffStatsResponse {
     added: usize,
     removed: usize,
     files_changed: usize,
+    base_commit: String,
+    files: Vec<GitDiffSummaryFile>,
 }
 
 #[derive(Serialize)]
@@ -54,6 +67,24 @@ struct PtySession {
     master: Box<dyn portable_pty::MasterPty + Send>,
     writer: Box<dyn Write + Send>,
     child: Box<dyn portable_pty::Child + Send>,
+    cgroup: Option<PathBuf>,
+    scrollback: Arc<Mutex<Vec<u8>>>,
+}
+
+impl PtySession {
+    fn terminate(mut self) {
+        let _ = self.child.kill();
+        if let Some(cgroup) = &self.cgroup {
+            pty_limits::kill_session_cgroup(cgroup);
+        }
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct TranscriptExportResponse {
+    content: String,
+    path: Option<String>,
 }
 
 #[derive(Default)]
@@ -104,80 +135,6 @@ fn shell_command() -> CommandBuilder {
     }
 }
 
-fn delta_status_label(status: Delta) -> &'static str {
-    match status {
-        Delta::Added => "added",
-        Delta::Modified => "modified",
-        Delta::Deleted => "deleted",
-        Delta::Renamed => "renamed",
-        Delta::Copied => "copied",
-        Delta::Typechange => "typechange",
-        Delta::Untracked => "untracked",
-        Delta::Conflicted => "conflicted",
-        Delta::Ignored => "ignored",
-        Delta::Unreadable => "unreadable",
-        _ => "unknown",
-    }
-}
-
-fn read_blob_content(repo: &Repository, oid: Oid) -> Option<Vec<u8>> {
-    if oid.is_zero() {
-        return None;
-    }
-    repo.find_blob(oid).ok().map(|blob| blob.content().to_vec())
-}
-
-fn read_workdir_content(root: &Path, rel_path: &str) -> Option<Vec<u8>> {
-    let path = root.join(rel_path);
-    std::fs::read(path).ok()
-}
-
-fn is_binary_content(bytes: &[u8]) -> bool {
-    bytes.iter().any(|byte| *byte == 0)
-}
-
-fn count_lines(bytes: &[u8]) -> usize {
-    if bytes.is_empty() {
-        return 0;
-    }
-    let mut count = bytes.iter().filter(|byte| **byte == b'\n').count();
-    if bytes.last() != Some(&b'\n') {
-        count += 1;
-    }
-    count
-}
-
-fn collect_files(path: &Path, files: &mut Vec<PathBuf>) {
-    if path.is_file() {
-        files.push(path.to_path_buf());
-        return;
-    }
-    if let Ok(entries) = std::fs::read_dir(path) {
-        for entry in entries.flatten() {
-            collect_files(&entry.path(), files);
-        }
-    }
-}
-
-fn get_untracked_files(repo_path: &str, workdir: &Path) -> Result<Vec<PathBuf>, String> {
-    let output = run_git_command_output(
-        repo_path,
-        &["status", "--porcelain=v1", "-z", "--untracked-files=all"],
-    )?;
-    let mut files = Vec::new();
-    for entry in output.split('\0') {
-        if entry.starts_with("?? ") {
-            let rel_path = entry.trim_start_matches("?? ").trim();
-            if rel_path.is_empty() {
-                continue;
-            }
-            let full_path = workdir.join(rel_path);
-            collect_files(&full_path, &mut files);
-        }
-    }
-    Ok(files)
-}
-
 fn resolve_base_commit<'repo>(
     repo: &'repo Repository,
     base_branch: &str,
@@ -443,138 +400,56 @@ fn save_task_groups(
 }
 
 #[tauri::command]
-fn get_git_diff(path: String) -> Result<GitDiffResponse, String> {
+fn get_git_diff(
+    path: String,
+    options: Option<GitDiffRequestOptions>,
+) -> Result<GitDiffResponse, String> {
+    let options = options.unwrap_or_default();
     let repo = Repository::discover(&path).map_err(|error| error.to_string())?;
     let root = repo
         .workdir()
         .ok_or_else(|| "repository has no working directory".to_string())?;
 
-    let mut diff_opts = DiffOptions::new();
-    diff_opts
-        .include_untracked(true)
-        .recurse_untracked_dirs(true)
-        .include_typechange(true);
-
-    let diff = match repo.head().ok().and_then(|head| head.peel_to_tree().ok()) {
-        Some(tree) => repo
-            .diff_tree_to_workdir_with_index(Some(&tree), Some(&mut diff_opts))
-            .map_err(|error| error.to_string())?,
-        None => repo
-            .diff_index_to_workdir(None, Some(&mut diff_opts))
-            .map_err(|error| error.to_string())?,
-    };
+    let mut diff_opts = git_diff::workdir_diff_options(&options);
+    let diff = git_diff::workdir_diff(&repo, None, &options, &mut diff_opts)?;
 
-    let mut files = Vec::new();
-    for delta in diff.deltas() {
-        let path = delta
-            .new_file()
-            .path()
-            .or_else(|| delta.old_file().path());
-        let Some(path) = path else {
-            continue;
-        };
-        let path = path.to_string_lossy().to_string();
-
-        let old_bytes = read_blob_content(&repo, delta.old_file().id());
-        let new_bytes = read_workdir_content(root, &path);
-
-        let old_is_binary = old_bytes
-            .as_ref()
-            .map(|bytes| is_binary_content(bytes))
-            .unwrap_or(false);
-        let new_is_binary = new_bytes
-            .as_ref()
-            .map(|bytes| is_binary_content(bytes))
-            .unwrap_or(false);
-        let is_binary = old_is_binary || new_is_binary;
-
-        let old_content = if is_binary {
-            String::new()
-        } else {
-            old_bytes
-                .as_ref()
-                .map(|bytes| String::from_utf8_lossy(bytes).to_string())
-                .unwrap_or_default()
-        };
-        let new_content = if is_binary {
-            String::new()
-        } else {
-            new_bytes
-                .as_ref()
-                .map(|bytes| String::from_utf8_lossy(bytes).to_string())
-                .unwrap_or_default()
-        };
-
-        files.push(GitDiffFile {
-            path,
-            status: delta_status_label(delta.status()).to_string(),
-            old_content,
-            new_content,
-            is_binary,
-        });
-    }
+    let (files, skipped) = git_diff::collect_diff_files(&repo, root, &diff, &options)?;
 
     Ok(GitDiffResponse {
         root: root.to_string_lossy().to_string(),
+        base_commit: None,
         files,
+        skipped,
     })
 }
 
 #[tauri::command]
-fn get_git_diff_stats(path: String, base_branch: String) -> Result<GitDiffStatsResponse, String> {
-    let output = run_git_command_output(
-        &path,
-        &["diff", "--numstat", &base_branch],
-    )?;
-
-    let mut added = 0usize;
-    let mut removed = 0usize;
-    let mut files_changed = 0usize;
-
-    for line in output.lines() {
-        let mut parts = line.split('\t');
-        let added_part = parts.next().unwrap_or("");
-        let removed_part = parts.next().unwrap_or("");
-        let file_part = parts.next().unwrap_or("");
-        if file_part.is_empty() {
-            continue;
-        }
-        files_changed += 1;
-        if added_part == "-" || removed_part == "-" {
-            continue;
-        }
-        if let Ok(value) = added_part.parse::<usize>() {
-            added += value;
-        }
-        if let Ok(value) = removed_part.parse::<usize>() {
-            removed += value;
-        }
-    }
+fn get_git_diff_stats(
+    path: String,
+    base_branch: String,
+    three_dot: Option<bool>,
+) -> Result<GitDiffStatsResponse, String> {
+    let repo = Repository::discover(&path).map_err(|error| error.to_string())?;
+    let base = if three_dot.unwrap_or(false) {
+        git_diff::merge_base_commit(&repo, &base_branch)?
+    } else {
+        resolve_base_commit(&repo, &base_branch)?
+    };
+    let base_tree = base.tree().map_err(|error| error.to_string())?;
 
-    if let Ok(repo) = Repository::discover(&path) {
-        if let Some(workdir) = repo.workdir() {
-            if let Ok(untracked_files) = get_untracked_files(&path, workdir) {
-                for file_path in untracked_files {
-                    if let Ok(bytes) = std::fs::read(&file_path) {
-                        if is_binary_content(&bytes) {
-                            files_changed += 1;
-                            continue;
-                        }
-                        let line_count = count_lines(&bytes);
-                        if line_count > 0 {
-                            added += line_count;
-                        }
-                        files_changed += 1;
-                    }
-                }
-            }
-        }
-    }
+    // Untracked files count as fully added, so their contents are needed.
+    let options = GitDiffRequestOptions::default();
+    let mut diff_opts = git_diff::workdir_diff_options(&options);
+    diff_opts.show_untracked_content(true);
+    let diff = git_diff::tree_workdir_diff(&repo, Some(&base_tree), &options, &mut diff_opts)?;
+    let (files, _) = git_diff::summarize_diff(&repo, &diff, &options)?;
 
     Ok(GitDiffStatsResponse {
-        added,
-        removed,
-        files_changed,
+        added: files.iter().map(|file| file.added).sum(),
+        removed: files.iter().map(|file| file.removed).sum(),
+        files_changed: files.len(),
+        base_commit: base.id().to_string(),
+        files,
     })
 }
 
@@ -590,80 +465,93 @@ fn get_git_branch(path: String) -> Result<String, String> {
 }
 
 #[tauri::command]
-fn get_git_diff_base(path: String, base_branch: String) -> Result<GitDiffResponse, String> {
+fn get_git_diff_base(
+    path: String,
+    base_branch: String,
+    options: Option<GitDiffRequestOptions>,
+) -> Result<GitDiffResponse, String> {
+    let options = options.unwrap_or_default();
     let repo = Repository::discover(&path).map_err(|error| error.to_string())?;
     let root = repo
         .workdir()
         .ok_or_else(|| "repository has no working directory".to_string())?;
-    let base_commit = resolve_base_commit(&repo, &base_branch)?;
-    let base_tree = base_commit.tree().map_err(|error| error.to_string())?;
+    let base = git_diff::comparison_commit(&repo, &base_branch, &options)?;
+    let base_tree = base.tree().map_err(|error| error.to_string())?;
+    let mut diff_opts = git_diff::workdir_diff_options(&options);
+    let diff = git_diff::tree_workdir_diff(&repo, Some(&base_tree), &options, &mut diff_opts)?;
 
-    let mut diff_opts = DiffOptions::new();
-    diff_opts
-        .include_untracked(true)
-        .recurse_untracked_dirs(true)
-        .include_typechange(true);
+    let (files, skipped) = git_diff::collect_diff_files(&repo, root, &diff, &options)?;
 
-    let diff = repo
-        .diff_tree_to_workdir_with_index(Some(&base_tree), Some(&mut diff_opts))
-        .map_err(|error| error.to_string())?;
+    Ok(GitDiffResponse {
+        root: root.to_string_lossy().to_string(),
+        base_commit: Some(base.id().to_string()),
+        files,
+        skipped,
+    })
+}
 
-    let mut files = Vec::new();
-    for delta in diff.deltas() {
-        let path = delta
-            .new_file()
-            .path()
-            .or_else(|| delta.old_file().path());
-        let Some(path) = path else {
-            continue;
-        };
-        let path = path.to_string_lossy().to_string();
-
-        let old_bytes = read_blob_content(&repo, delta.old_file().id());
-        let new_bytes = read_workdir_content(root, &path);
-
-        let old_is_binary = old_bytes
-            .as_ref()
-            .map(|bytes| is_binary_content(bytes))
-            .unwrap_or(false);
-        let new_is_binary = new_bytes
-            .as_ref()
-            .map(|bytes| is_binary_content(bytes))
-            .unwrap_or(false);
-        let is_binary = old_is_binary || new_is_binary;
-
-        let old_content = if is_binary {
-            String::new()
-        } else {
-            old_bytes
-                .as_ref()
-                .map(|bytes| String::from_utf8_lossy(bytes).to_string())
-                .unwrap_or_default()
-        };
-        let new_content = if is_binary {
-            String::new()
-        } else {
-            new_bytes
-                .as_ref()
-                .map(|bytes| String::from_utf8_lossy(bytes).to_string())
-                .unwrap_or_default()
-        };
-
-        files.push(GitDiffFile {
-            path,
-            status: delta_status_label(delta.status()).to_string(),
-            old_content,
-            new_content,
-            is_binary,
-        });
-    }
+#[tauri::command]
+fn get_git_diff_summary(
+    path: String,
+    base_branch: Option<String>,
+    options: Option<GitDiffRequestOptions>,
+) -> Result<GitDiffSummaryResponse, String> {
+    let options = options.unwrap_or_default();
+    let repo = Repository::discover(&path).map_err(|error| error.to_string())?;
+    let root = repo
+        .workdir()
+        .ok_or_else(|| "repository has no working directory".to_string())?;
 
-    Ok(GitDiffResponse {
+    // Untracked contents are needed for numstat, but never leave the backend.
+    let mut diff_opts = git_diff::workdir_diff_options(&options);
+    diff_opts.show_untracked_content(true);
+    let diff = git_diff::workdir_diff(&repo, base_branch.as_deref(), &options, &mut diff_opts)?;
+    let (files, skipped) = git_diff::summarize_diff(&repo, &diff, &options)?;
+
+    Ok(GitDiffSummaryResponse {
         root: root.to_string_lossy().to_string(),
         files,
+        skipped,
     })
 }
 
+/// Loads one file of a `get_git_diff_summary` listing. Pass the summary's
+/// `oldPath` for renamed files and the same options, so the file is paired
+/// the way the summary paired it.
+#[tauri::command]
+fn get_git_diff_file(
+    path: String,
+    file_path: String,
+    old_path: Option<String>,
+    base_branch: Option<String>,
+    options: Option<GitDiffRequestOptions>,
+) -> Result<GitDiffFile, String> {
+    let options = options.unwrap_or_default();
+    let repo = Repository::discover(&path).map_err(|error| error.to_string())?;
+    let root = repo
+        .workdir()
+        .ok_or_else(|| "repository has no working directory".to_string())?;
+
+    // Only the file itself, plus the other side of a rename so rename
+    // detection still pairs them.
+    let mut paths = vec![file_path.clone()];
+    paths.extend(old_path.filter(|old_path| *old_path != file_path));
+    let options = GitDiffRequestOptions {
+        include_paths: Some(paths),
+        exclude_paths: None,
+        ..options
+    };
+    let mut diff_opts = git_diff::workdir_diff_options(&options);
+    diff_opts.disable_pathspec_match(true);
+    let diff = git_diff::workdir_diff(&repo, base_branch.as_deref(), &options, &mut diff_opts)?;
+
+    git_diff::find_delta_index(&diff, &file_path)
+        .map(|index| git_diff::collect_diff_file(&repo, root, &diff, index, &options))
+        .transpose()?
+        .flatten()
+        .ok_or_else(|| format!("no changes for '{}'", file_path))
+}
+
 #[tauri::command]
 fn get_file_section(
     root_path: Option<String>,
@@ -747,6 +635,7 @@ fn spawn_pty(
     id: String,
     cols: u16,
     rows: u16,
+    limits: Option<PtyResourceLimits>,
     state: State<PtyState>,
     app: tauri::AppHandle,
 ) -> Result<(), String> {
@@ -755,10 +644,11 @@ fn spawn_pty(
             .sessions
             .lock()
             .map_err(|_| "terminal state poisoned".to_string())?;
-        if let Some(mut session) = sessions.remove(&id) {
-            let _ = session.child.kill();
+        if let Some(session) = sessions.remove(&id) {
+            session.terminate();
         }
     }
+    let limits = limits.unwrap_or_default();
 
     let pty_system = native_pty_system();
     let pair = pty_system
@@ -781,10 +671,36 @@ fn spawn_pty(
         cmd.cwd(home);
     }
 
-    let child = pair
-        .slave
-        .spawn_command(cmd)
-        .map_err(|error| error.to_string())?;
+    // cgroup caps are best effort: without delegation the session still runs
+    // with its rlimits and the UI is told the caps were not applied.
+    let cgroup = pty_limits::create_session_cgroup(&id, &limits).unwrap_or_else(|error| {
+        pty_limits::emit_limit_event(
+            &app,
+            &id,
+            "cgroup",
+            format!("cgroup limits not applied: {}", error),
+        );
+        None
+    });
+    let spawned =
+        pty_limits::wrap_session_command(cmd, &limits, cgroup.as_deref()).and_then(|cmd| {
+            pair.slave
+                .spawn_command(cmd)
+                .map_err(|error| error.to_string())
+        });
+    let child = match spawned {
+        Ok(child) => child,
+        Err(error) => {
+            if let Some(cgroup) = &cgroup {
+                pty_limits::remove_session_cgroup(cgroup);
+            }
+            return Err(error);
+        }
+    };
+    let child_pid = child.process_id();
+    if let Some(cgroup) = &cgroup {
+        pty_limits::monitor_session_cgroup(app.clone(), id.clone(), cgroup.clone());
+    }
 
     let mut reader = pair
         .master
@@ -795,14 +711,21 @@ fn spawn_pty(
         .take_writer()
         .map_err(|error| error.to_string())?;
 
+    let scrollback = Arc::new(Mutex::new(Vec::new()));
+    let scrollback_clone = Arc::clone(&scrollback);
     let id_clone = id.clone();
     let app_handle = app.clone();
+    let mut limit_scanner = pty_limits::OutputScanner::new(&limits);
     std::thread::spawn(move || {
         let mut buffer = [0u8; 8192];
         loop {
             match reader.read(&mut buffer) {
                 Ok(0) => break,
                 Ok(bytes) => {
+                    append_scrollback(&scrollback_clone, &buffer[..bytes]);
+                    for (limit, line) in limit_scanner.scan(&buffer[..bytes]) {
+                        pty_limits::emit_limit_event(&app_handle, &id_clone, limit, line);
+                    }
                     let data = String::from_utf8_lossy(&buffer[..bytes]).to_string();
                     let _ = app_handle.emit(
                         "terminal-output",
@@ -815,6 +738,7 @@ fn spawn_pty(
                 Err(_) => break,
             }
         }
+        report_session_exit(&app_handle, &id_clone, child_pid);
     });
 
     let mut sessions = state
@@ -827,11 +751,55 @@ fn spawn_pty(
             master: pair.master,
             writer,
             child,
+            cgroup,
+            scrollback,
         },
     );
     Ok(())
 }
 
+fn append_scrollback(scrollback: &Mutex<Vec<u8>>, bytes: &[u8]) {
+    let Ok(mut buffer) = scrollback.lock() else {
+        return;
+    };
+    buffer.extend_from_slice(bytes);
+    if buffer.len() > SCROLLBACK_LIMIT_BYTES {
+        // Drop whole lines from the front so the kept output starts cleanly.
+        let overflow = buffer.len() - SCROLLBACK_LIMIT_BYTES;
+        let cut = buffer[overflow..]
+            .iter()
+            .position(|byte| *byte == b'\n')
+            .map(|offset| overflow + offset + 1)
+            .unwrap_or(overflow);
+        buffer.drain(..cut);
+    }
+}
+
+fn report_session_exit(app: &tauri::AppHandle, id: &str, pid: Option<u32>) {
+    let state = app.state::<PtyState>();
+    for _ in 0..10 {
+        {
+            let Ok(mut sessions) = state.sessions.lock() else {
+                return;
+            };
+            let Some(session) = sessions.get_mut(id) else {
+                return;
+            };
+            // The id may already belong to a respawned session.
+            if session.child.process_id() != pid {
+                return;
+            }
+            if let Ok(Some(status)) = session.child.try_wait() {
+                if let Some(limit) = pty_limits::exit_limit_hit(&status) {
+                    pty_limits::emit_limit_event(app, id, limit, status.to_string());
+                }
+                return;
+            }
+        }
+        std::thread::sleep(std::time::Duration::from_millis(50));
+    }
+}
+
 #[tauri::command]
 fn write_pty(id: String, data: String, state: State<PtyState>) -> Result<(), String> {
     let mut sessions = state
@@ -879,12 +847,85 @@ fn close_pty(id: String, state: State<PtyState>) -> Result<(), String> {
         .sessions
         .lock()
         .map_err(|_| "terminal state poisoned".to_string())?;
-    if let Some(mut session) = sessions.remove(&id) {
-        let _ = session.child.kill();
+    if let Some(session) = sessions.remove(&id) {
+        session.terminate();
     }
     Ok(())
 }
 
+#[tauri::command]
+async fn export_pty_transcript(
+    id: String,
+    format: String,
+    destination: Option<String>,
+    save_dialog: Option<bool>,
+    state: State<'_, PtyState>,
+    app: tauri::AppHandle,
+) -> Result<TranscriptExportResponse, String> {
+    let bytes = {
+        let sessions = state
+            .sessions
+            .lock()
+            .map_err(|_| "terminal state poisoned".to_string())?;
+        let session = sessions
+            .get(&id)
+            .ok_or_else(|| "missing terminal session".to_string())?;
+        let scrollback = session
+            .scrollback
+            .lock()
+            .map_err(|_| "terminal scrollback poisoned".to_string())?;
+        scrollback.clone()
+    };
+
+    // Rendering, the save dialog and the write all block; keep them off the
+    // async runtime's workers.
+    tauri::async_runtime::spawn_blocking(move || {
+        export_transcript(&id, &format, &bytes, destination, save_dialog, &app)
+    })
+    .await
+    .map_err(|error| error.to_string())?
+}
+
+fn export_transcript(
+    id: &str,
+    format: &str,
+    bytes: &[u8],
+    destination: Option<String>,
+    save_dialog: Option<bool>,
+    app: &tauri::AppHandle,
+) -> Result<TranscriptExportResponse, String> {
+    let title = format!("Terminal transcript ({})", id);
+    let (content, extension) = match format {
+        "text" => (transcript::render_text(bytes), "txt"),
+        "html" => (transcript::render_html(bytes, &title), "html"),
+        "markdown" => (transcript::render_markdown(bytes, &title), "md"),
+        other => return Err(format!("unsupported transcript format '{}'", other)),
+    };
+
+    let target = match destination.filter(|value| !value.trim().is_empty()) {
+        Some(path) => Some(PathBuf::from(path)),
+        None if save_dialog.unwrap_or(false) => app
+            .dialog()
+            .file()
+            .set_title("Export terminal transcript")
+            .set_file_name(format!("transcript-{}.{}", id, extension))
+            .add_filter(format, &[extension])
+            .blocking_save_file()
+            .map(|path| path.into_path().map_err(|error| error.to_string()))
+            .transpose()?,
+        None => None,
+    };
+
+    if let Some(target) = &target {
+        std::fs::write(target, &content).map_err(|error| error.to_string())?;
+    }
+
+    Ok(TranscriptExportResponse {
+        content,
+        path: target.map(|path| path.to_string_lossy().to_string()),
+    })
+}
+
 #[tauri::command]
 async fn create_panel_window(
     app: tauri::AppHandle,
@@ -930,6 +971,7 @@ pub fn run() {
     tauri::Builder::default()
         .manage(PtyState::default())
         .manage(McpTaskServerState::default())
+        .manage(git_watch::GitWatchState::default())
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_opener::init())
         .plugin(tauri_plugin_process::init())
@@ -950,6 +992,7 @@ pub fn run() {
             write_pty,
             resize_pty,
             close_pty,
+            export_pty_transcript,
             get_mcp_task_server_command,
             load_task_groups,
             save_task_groups,
@@ -957,8 +1000,38 @@ pub fn run() {
             get_git_diff_stats,
             get_git_branch,
             get_git_diff_base,
+            get_git_diff_summary,
+            get_git_diff_file,
+            git_index::git_stage,
+            git_index::git_unstage,
+            git_index::git_stage_selection,
+            git_index::git_unstage_selection,
             get_file_section,
             reset_task_git,
+            git_commit::git_commit,
+            git_discard::git_discard,
+            git_discard::git_discard_selection,
+            git_log::get_git_log,
+            git_merge::merge_task_branch,
+            git_rebase::rebase_task_branch,
+            git_conflict::get_git_conflicts,
+            git_conflict::resolve_git_conflict,
+            git_conflict::mark_git_conflict_resolved,
+            git_patch::export_task_patch,
+            git_patch::apply_task_changes,
+            git_watch::watch_git_status,
+            git_watch::unwatch_git_status,
+            git_history::get_file_section_history,
+            git_branch::list_git_branches,
+            git_branch::create_git_branch,
+            git_branch::rename_git_branch,
+            git_branch::checkout_git_branch,
+            git_branch::delete_git_branch,
+            git_push::git_push,
+            git_stash::git_stash_save,
+            git_stash::list_git_stashes,
+            git_stash::apply_git_stash,
+            git_stash::drop_git_stash,
             create_panel_window,
             close_panel_window
         ])
@@ -0,0 +1,552 @@
+use portable_pty::CommandBuilder;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+use tauri::Emitter;
+
+const CGROUP_POLL_INTERVAL: Duration = Duration::from_secs(1);
+const CGROUP_CPU_PERIOD_US: u64 = 100_000;
+/// Polls to wait for the session shell to join its cgroup before an empty
+/// cgroup counts as finished.
+const CGROUP_JOIN_POLLS: u32 = 10;
+/// Leaf the app moves itself into so its own cgroup can delegate controllers.
+const APP_LEAF_CGROUP: &str = "prompter-app";
+/// Environment variable that tells the session wrapper which cgroup to join.
+const CGROUP_PROCS_ENV: &str = "PROMPTER_CGROUP_PROCS";
+const OUTPUT_REPORT_INTERVAL: Duration = Duration::from_secs(5);
+const OUTPUT_LINE_LIMIT: usize = 4096;
+
+/// Hard caps applied to a PTY session and everything it spawns.
+///
+/// The rlimits are inherited by every descendant of the session shell. The
+/// cgroup caps are only applied when cgroup v2 delegation is available for
+/// this process (or for `DESKTOP_PROMPTER_CGROUP_ROOT`).
+///
+/// Hits are reported as `terminal-limit` events: cgroup memory and CPU hits
+/// from the cgroup counters, the session shell dying of SIGXCPU from its exit
+/// status, and rlimit failures in any process from the error messages they
+/// print (see [`OutputScanner`]).
+#[derive(Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PtyResourceLimits {
+    pub max_address_space_bytes: Option<u64>,
+    pub max_open_files: Option<u64>,
+    pub max_cpu_seconds: Option<u64>,
+    pub memory_max_bytes: Option<u64>,
+    /// Share of a single CPU, e.g. `150` allows one and a half cores.
+    pub cpu_max_percent: Option<u64>,
+}
+
+impl PtyResourceLimits {
+    fn has_rlimits(&self) -> bool {
+        self.max_address_space_bytes.is_some()
+            || self.max_open_files.is_some()
+            || self.max_cpu_seconds.is_some()
+    }
+
+    fn has_cgroup_limits(&self) -> bool {
+        self.memory_max_bytes.is_some() || self.cpu_max_percent.is_some()
+    }
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TerminalLimitEvent {
+    pub id: String,
+    pub limit: String,
+    pub message: String,
+}
+
+pub fn emit_limit_event(app: &tauri::AppHandle, id: &str, limit: &str, message: String) {
+    let _ = app.emit(
+        "terminal-limit",
+        TerminalLimitEvent {
+            id: id.to_string(),
+            limit: limit.to_string(),
+            message,
+        },
+    );
+}
+
+/// Shell snippet that applies one limit or fails the session with a message
+/// the user sees in the terminal.
+#[cfg(unix)]
+fn ulimit_step(flag: &str, value: u64, name: &str) -> String {
+    format!(
+        "ulimit {} {} || {{ echo 'cannot apply the {} limit' >&2; exit 126; }}; ",
+        flag, value, name
+    )
+}
+
+/// Wraps the session shell in `/bin/sh`, which joins the session cgroup and
+/// sets the rlimits right before the shell is exec'd, so nothing the shell
+/// does runs uncapped. If a limit cannot be applied the session fails
+/// instead of silently running without it.
+#[cfg(unix)]
+pub fn wrap_session_command(
+    cmd: CommandBuilder,
+    limits: &PtyResourceLimits,
+    cgroup: Option<&Path>,
+) -> Result<CommandBuilder, String> {
+    if !limits.has_rlimits() && cgroup.is_none() {
+        return Ok(cmd);
+    }
+
+    let mut script = String::new();
+    if cgroup.is_some() {
+        script.push_str(&format!(
+            "echo $$ > \"${0}\" || {{ echo 'cannot join the session cgroup' >&2; exit 126; }}; unset {0}; ",
+            CGROUP_PROCS_ENV
+        ));
+    }
+    if let Some(bytes) = limits.max_address_space_bytes {
+        // macOS does not enforce RLIMIT_AS, and its `ulimit -v` fails.
+        if cfg!(target_os = "macos") {
+            return Err("address-space limits are not supported on macOS".to_string());
+        }
+        script.push_str(&ulimit_step("-v", (bytes / 1024).max(1), "address-space"));
+    }
+    if let Some(files) = limits.max_open_files {
+        script.push_str(&ulimit_step("-n", files.max(1), "open-files"));
+    }
+    if let Some(seconds) = limits.max_cpu_seconds {
+        script.push_str(&ulimit_step("-t", seconds.max(1), "cpu-time"));
+    }
+    script.push_str("exec \"$@\"");
+
+    let mut wrapped = CommandBuilder::new("/bin/sh");
+    wrapped.arg("-c");
+    wrapped.arg(script);
+    wrapped.arg("sh");
+    for arg in cmd.get_argv() {
+        wrapped.arg(arg);
+    }
+    // The wrapper replaces the original command, so it must carry the same
+    // environment and working directory.
+    for (key, value) in cmd.iter_extra_env_as_str() {
+        wrapped.env(key, value);
+    }
+    if let Some(cwd) = cmd.get_cwd() {
+        wrapped.cwd(cwd);
+    }
+    if let Some(cgroup) = cgroup {
+        wrapped.env(CGROUP_PROCS_ENV, cgroup.join("cgroup.procs"));
+    }
+    Ok(wrapped)
+}
+
+#[cfg(not(unix))]
+pub fn wrap_session_command(
+    cmd: CommandBuilder,
+    limits: &PtyResourceLimits,
+    _cgroup: Option<&Path>,
+) -> Result<CommandBuilder, String> {
+    if limits.has_rlimits() {
+        return Err("resource limits are not supported on this platform".to_string());
+    }
+    Ok(cmd)
+}
+
+/// The signal that terminated a process. portable-pty only keeps the
+/// signal's `strsignal` description, so it is mapped back to its number.
+#[cfg(unix)]
+fn exit_signal(status: &portable_pty::ExitStatus) -> Option<libc::c_int> {
+    let description = status.to_string();
+    let name = description.strip_prefix("Terminated by ")?;
+    if let Some(number) = name.strip_prefix("Signal ") {
+        return number.parse().ok();
+    }
+    (1..65).find(|signal| {
+        let signal_name = unsafe { libc::strsignal(*signal) };
+        !signal_name.is_null()
+            && unsafe { std::ffi::CStr::from_ptr(signal_name) }.to_string_lossy() == name
+    })
+}
+
+/// Returns the limit name when a session shell was killed by one of its own
+/// rlimits.
+///
+/// Only the session shell's exit is seen here. Commands the shell runs that
+/// hit a limit are caught by [`OutputScanner`] from what gets printed.
+#[cfg(unix)]
+pub fn exit_limit_hit(status: &portable_pty::ExitStatus) -> Option<&'static str> {
+    match exit_signal(status)? {
+        libc::SIGXCPU => Some("cpu-time"),
+        _ => None,
+    }
+}
+
+#[cfg(not(unix))]
+pub fn exit_limit_hit(_status: &portable_pty::ExitStatus) -> Option<&'static str> {
+    None
+}
+
+/// The cgroup this process runs in, from `/proc/self/cgroup`.
+fn own_cgroup() -> Option<PathBuf> {
+    let membership = std::fs::read_to_string("/proc/self/cgroup").ok()?;
+    let relative = membership
+        .lines()
+        .find_map(|line| line.strip_prefix("0::"))?
+        .trim_start_matches('/');
+    let candidate = Path::new("/sys/fs/cgroup").join(relative);
+    candidate.is_dir().then_some(candidate)
+}
+
+fn cgroup_processes(cgroup: &Path) -> Vec<String> {
+    std::fs::read_to_string(cgroup.join("cgroup.procs"))
+        .map(|procs| procs.lines().map(str::to_string).collect())
+        .unwrap_or_default()
+}
+
+/// Prepares the cgroup that session cgroups are created under.
+///
+/// cgroup v2 only hands controllers down from a cgroup that has no processes
+/// of its own, so when the app's own cgroup is used, the processes in it (the
+/// app and anything it started) first move into a leaf next to the sessions.
+fn prepare_cgroup_root() -> Result<PathBuf, String> {
+    if let Ok(explicit) = std::env::var("DESKTOP_PROMPTER_CGROUP_ROOT") {
+        let candidate = PathBuf::from(explicit);
+        if candidate.is_dir() {
+            enable_controllers(&candidate)?;
+            return Ok(candidate);
+        }
+    }
+    let root = own_cgroup().ok_or_else(|| "cgroup v2 is not available".to_string())?;
+    let processes = cgroup_processes(&root);
+    if !processes.is_empty() {
+        let leaf = root.join(APP_LEAF_CGROUP);
+        std::fs::create_dir_all(&leaf)
+            .map_err(|error| format!("cannot create cgroup '{}': {}", leaf.display(), error))?;
+        for pid in processes {
+            // Processes that exit meanwhile cannot be moved; any other
+            // leftover makes enabling the controllers fail below.
+            let _ = std::fs::write(leaf.join("cgroup.procs"), pid);
+        }
+        if !cgroup_processes(&root).is_empty() {
+            return Err(format!(
+                "cgroup '{}' holds processes that cannot be moved; set DESKTOP_PROMPTER_CGROUP_ROOT to a delegated cgroup",
+                root.display()
+            ));
+        }
+    }
+    enable_controllers(&root)?;
+    Ok(root)
+}
+
+fn cgroup_root() -> Result<PathBuf, String> {
+    static ROOT: OnceLock<Result<PathBuf, String>> = OnceLock::new();
+    ROOT.get_or_init(prepare_cgroup_root).clone()
+}
+
+fn enable_controllers(root: &Path) -> Result<(), String> {
+    let available = std::fs::read_to_string(root.join("cgroup.controllers"))
+        .map_err(|error| error.to_string())?;
+    let enabled = std::fs::read_to_string(root.join("cgroup.subtree_control"))
+        .map_err(|error| error.to_string())?;
+    for controller in ["memory", "cpu"] {
+        if !available.split_whitespace().any(|name| name == controller) {
+            return Err(format!(
+                "cgroup controller '{}' is not delegated",
+                controller
+            ));
+        }
+        if enabled.split_whitespace().any(|name| name == controller) {
+            continue;
+        }
+        std::fs::write(
+            root.join("cgroup.subtree_control"),
+            format!("+{}", controller),
+        )
+        .map_err(|error| {
+            format!(
+                "cannot enable cgroup controller '{}': {}",
+                controller, error
+            )
+        })?;
+    }
+    Ok(())
+}
+
+/// Creates a per-session cgroup with `memory.max` / `cpu.max`. The session
+/// wrapper moves the shell into it before exec (see
+/// [`wrap_session_command`]), and everything the shell forks inherits it.
+pub fn create_session_cgroup(
+    id: &str,
+    limits: &PtyResourceLimits,
+) -> Result<Option<PathBuf>, String> {
+    static COUNTER: AtomicUsize = AtomicUsize::new(0);
+    if !limits.has_cgroup_limits() || !cfg!(target_os = "linux") {
+        return Ok(None);
+    }
+    let root = cgroup_root()?;
+
+    let safe_id: String = id
+        .chars()
+        .map(|ch| {
+            if ch.is_ascii_alphanumeric() || ch == '-' {
+                ch
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    let cgroup = root.join(format!(
+        "prompter-pty-{}-{}-{}",
+        safe_id,
+        std::process::id(),
+        COUNTER.fetch_add(1, Ordering::Relaxed)
+    ));
+    std::fs::create_dir_all(&cgroup).map_err(|error| error.to_string())?;
+
+    let configure = || -> Result<(), String> {
+        if let Some(bytes) = limits.memory_max_bytes {
+            std::fs::write(cgroup.join("memory.max"), bytes.to_string())
+                .map_err(|error| error.to_string())?;
+        }
+        if let Some(percent) = limits.cpu_max_percent {
+            let quota = (CGROUP_CPU_PERIOD_US * percent.max(1)) / 100;
+            std::fs::write(
+                cgroup.join("cpu.max"),
+                format!("{} {}", quota, CGROUP_CPU_PERIOD_US),
+            )
+            .map_err(|error| error.to_string())?;
+        }
+        Ok(())
+    };
+    if let Err(error) = configure() {
+        let _ = std::fs::remove_dir(&cgroup);
+        return Err(error);
+    }
+    Ok(Some(cgroup))
+}
+
+/// Removes a session cgroup whose shell never started.
+pub fn remove_session_cgroup(cgroup: &Path) {
+    let _ = std::fs::remove_dir(cgroup);
+}
+
+/// Kills every process left in the session cgroup. The monitor thread removes
+/// the directory once it is empty.
+pub fn kill_session_cgroup(cgroup: &Path) {
+    let _ = std::fs::write(cgroup.join("cgroup.kill"), "1");
+}
+
+fn read_counter(path: &Path, key: &str) -> Option<u64> {
+    let content = std::fs::read_to_string(path).ok()?;
+    content.lines().find_map(|line| {
+        let mut parts = line.split_whitespace();
+        if parts.next() == Some(key) {
+            parts.next().and_then(|value| value.parse().ok())
+        } else {
+            None
+        }
+    })
+}
+
+/// Polls the cgroup event counters and reports memory and CPU limit hits
+/// until the cgroup is empty, then removes it. The counters are read before
+/// checking for emptiness so an OOM kill of the last process is reported.
+pub fn monitor_session_cgroup(app: tauri::AppHandle, id: String, cgroup: PathBuf) {
+    std::thread::spawn(move || {
+        let events_path = cgroup.join("memory.events");
+        let cpu_stat_path = cgroup.join("cpu.stat");
+        let mut memory_max = read_counter(&events_path, "max").unwrap_or(0);
+        let mut oom_kill = read_counter(&events_path, "oom_kill").unwrap_or(0);
+        let mut throttled = read_counter(&cpu_stat_path, "nr_throttled").unwrap_or(0);
+        let mut was_throttled = false;
+        let mut polls_until_joined = Some(CGROUP_JOIN_POLLS);
+
+        loop {
+            std::thread::sleep(CGROUP_POLL_INTERVAL);
+            let populated = read_counter(&cgroup.join("cgroup.events"), "populated") == Some(1);
+
+            if let Some(value) = read_counter(&events_path, "oom_kill") {
+                if value > oom_kill {
+                    emit_limit_event(
+                        &app,
+                        &id,
+                        "memory",
+                        format!(
+                            "{} process(es) killed after reaching memory.max",
+                            value - oom_kill
+                        ),
+                    );
+                    oom_kill = value;
+                }
+            }
+            if let Some(value) = read_counter(&events_path, "max") {
+                if value > memory_max {
+                    emit_limit_event(
+                        &app,
+                        &id,
+                        "memory",
+                        "memory usage reached memory.max".to_string(),
+                    );
+                    memory_max = value;
+                }
+            }
+            if let Some(value) = read_counter(&cpu_stat_path, "nr_throttled") {
+                let is_throttled = value > throttled;
+                if is_throttled && !was_throttled {
+                    emit_limit_event(
+                        &app,
+                        &id,
+                        "cpu",
+                        "CPU usage throttled by cpu.max".to_string(),
+                    );
+                }
+                was_throttled = is_throttled;
+                throttled = value;
+            }
+
+            // The shell joins from its wrapper, shortly after spawn.
+            polls_until_joined = match polls_until_joined {
+                _ if populated => None,
+                Some(polls) if polls > 0 => Some(polls - 1),
+                _ => {
+                    let _ = std::fs::remove_dir(&cgroup);
+                    break;
+                }
+            };
+        }
+    });
+}
+
+/// Output that shows a process running into one of the session's rlimits.
+/// Failing allocations and opens do not kill anything, so the error text is
+/// the only trace; a limit is only matched when the session sets it.
+const OUTPUT_PATTERNS: [(&str, &[&str]); 3] = [
+    (
+        "address-space",
+        &[
+            "Cannot allocate memory",
+            "out of memory",
+            "memory allocation failed",
+            "bad_alloc",
+            "MemoryError",
+        ],
+    ),
+    ("open-files", &["Too many open files"]),
+    (
+        "cpu-time",
+        &["CPU time limit exceeded", "Cputime limit exceeded"],
+    ),
+];
+
+/// Watches PTY output line by line for processes reporting an rlimit hit,
+/// reporting each limit at most once per [`OUTPUT_REPORT_INTERVAL`].
+pub struct OutputScanner {
+    limits: Vec<&'static str>,
+    line: Vec<u8>,
+    reported: Vec<(&'static str, Instant)>,
+}
+
+impl OutputScanner {
+    pub fn new(limits: &PtyResourceLimits) -> Self {
+        let mut active = Vec::new();
+        if limits.max_address_space_bytes.is_some() {
+            active.push("address-space");
+        }
+        if limits.max_open_files.is_some() {
+            active.push("open-files");
+        }
+        if limits.max_cpu_seconds.is_some() {
+            active.push("cpu-time");
+        }
+        Self {
+            limits: active,
+            line: Vec::new(),
+            reported: Vec::new(),
+        }
+    }
+
+    /// Feeds a chunk of output and returns `(limit, line)` for each hit.
+    pub fn scan(&mut self, bytes: &[u8]) -> Vec<(&'static str, String)> {
+        let mut hits = Vec::new();
+        if self.limits.is_empty() {
+            return hits;
+        }
+        for byte in bytes {
+            if *byte != b'\n' && *byte != b'\r' {
+                if self.line.len() < OUTPUT_LINE_LIMIT {
+                    self.line.push(*byte);
+                }
+                continue;
+            }
+            let line = String::from_utf8_lossy(&self.line).trim().to_string();
+            self.line.clear();
+            if let Some(limit) = self.matching_limit(&line) {
+                hits.push((limit, line));
+            }
+        }
+        hits
+    }
+
+    fn matching_limit(&mut self, line: &str) -> Option<&'static str> {
+        let (limit, _) = OUTPUT_PATTERNS.iter().find(|(limit, patterns)| {
+            self.limits.contains(limit) && patterns.iter().any(|pattern| line.contains(pattern))
+        })?;
+        let now = Instant::now();
+        match self
+            .reported
+            .iter_mut()
+            .find(|(reported, _)| reported == limit)
+        {
+            Some((_, at)) if now.duration_since(*at) < OUTPUT_REPORT_INTERVAL => None,
+            Some((_, at)) => {
+                *at = now;
+                Some(limit)
+            }
+            None => {
+                self.reported.push((limit, now));
+                Some(limit)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scanner() -> OutputScanner {
+        OutputScanner::new(&PtyResourceLimits {
+            max_open_files: Some(64),
+            max_cpu_seconds: Some(10),
+            ..PtyResourceLimits::default()
+        })
+    }
+
+    #[test]
+    fn reports_limit_messages_split_across_chunks() {
+        let mut scanner = scanner();
+        assert!(scanner
+            .scan(b"ls: cannot open directory '.': Too many ")
+            .is_empty());
+        let hits = scanner.scan(b"open files\r\n$ ");
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].0, "open-files");
+        assert_eq!(
+            hits[0].1,
+            "ls: cannot open directory '.': Too many open files"
+        );
+    }
+
+    #[test]
+    fn ignores_limits_the_session_does_not_set() {
+        let mut scanner = scanner();
+        assert!(scanner
+            .scan(b"bash: fork: Cannot allocate memory\n")
+            .is_empty());
+    }
+
+    #[test]
+    fn throttles_repeated_reports() {
+        let mut scanner = scanner();
+        let output = b"CPU time limit exceeded (core dumped)\n";
+        assert_eq!(scanner.scan(output).len(), 1);
+        assert!(scanner.scan(output).is_empty());
+    }
+}
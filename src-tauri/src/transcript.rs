@@ -0,0 +1,468 @@
+//! Renders raw PTY output into shareable transcripts.
+//!
+//! The output is replayed through a minimal line-oriented terminal model so
+//! carriage-return redraws (spinners, progress bars) collapse to their final
+//! state instead of leaving every intermediate frame in the export.
+
+/// Furthest column a cursor movement can reach. Movements are clamped to it
+/// so a sequence like `ESC[9999999999C` cannot make the export pad a line
+/// with billions of cells.
+const MAX_CURSOR_COLUMN: usize = 4096;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Color {
+    Indexed(u8),
+    Rgb(u8, u8, u8),
+}
+
+#[derive(Clone, Copy, Default, PartialEq, Eq)]
+struct Style {
+    fg: Option<Color>,
+    bg: Option<Color>,
+    bold: bool,
+    dim: bool,
+    italic: bool,
+    underline: bool,
+    inverse: bool,
+}
+
+#[derive(Clone, Copy)]
+struct Cell {
+    ch: char,
+    style: Style,
+}
+
+#[derive(Default)]
+struct Screen {
+    lines: Vec<Vec<Cell>>,
+    col: usize,
+    style: Style,
+}
+
+impl Screen {
+    fn current_line(&mut self) -> &mut Vec<Cell> {
+        if self.lines.is_empty() {
+            self.lines.push(Vec::new());
+        }
+        self.lines.last_mut().expect("screen has a line")
+    }
+
+    fn put(&mut self, ch: char) {
+        let col = self.col;
+        let style = self.style;
+        let line = self.current_line();
+        while line.len() < col {
+            line.push(Cell {
+                ch: ' ',
+                style: Style::default(),
+            });
+        }
+        if col < line.len() {
+            line[col] = Cell { ch, style };
+        } else {
+            line.push(Cell { ch, style });
+        }
+        self.col += 1;
+    }
+
+    fn newline(&mut self) {
+        self.current_line();
+        self.lines.push(Vec::new());
+        self.col = 0;
+    }
+
+    fn erase_line(&mut self, mode: usize) {
+        let col = self.col;
+        let line = self.current_line();
+        match mode {
+            0 => line.truncate(col),
+            1 => {
+                for cell in line.iter_mut().take(col + 1) {
+                    cell.ch = ' ';
+                }
+            }
+            _ => line.clear(),
+        }
+    }
+
+    fn apply_sgr(&mut self, params: &[usize]) {
+        if params.is_empty() {
+            self.style = Style::default();
+            return;
+        }
+        let mut index = 0;
+        while index < params.len() {
+            let code = params[index];
+            match code {
+                0 => self.style = Style::default(),
+                1 => self.style.bold = true,
+                2 => self.style.dim = true,
+                3 => self.style.italic = true,
+                4 => self.style.underline = true,
+                7 => self.style.inverse = true,
+                22 => {
+                    self.style.bold = false;
+                    self.style.dim = false;
+                }
+                23 => self.style.italic = false,
+                24 => self.style.underline = false,
+                27 => self.style.inverse = false,
+                30..=37 => self.style.fg = Some(Color::Indexed((code - 30) as u8)),
+                39 => self.style.fg = None,
+                40..=47 => self.style.bg = Some(Color::Indexed((code - 40) as u8)),
+                49 => self.style.bg = None,
+                90..=97 => self.style.fg = Some(Color::Indexed((code - 90 + 8) as u8)),
+                100..=107 => self.style.bg = Some(Color::Indexed((code - 100 + 8) as u8)),
+                38 | 48 => {
+                    let (color, consumed) = parse_extended_color(&params[index + 1..]);
+                    if code == 38 {
+                        self.style.fg = color;
+                    } else {
+                        self.style.bg = color;
+                    }
+                    index += consumed;
+                }
+                _ => {}
+            }
+            index += 1;
+        }
+    }
+
+    fn apply_csi(&mut self, params: &[usize], action: char) {
+        let first = params.first().copied().unwrap_or(0);
+        match action {
+            'm' => self.apply_sgr(params),
+            'K' => self.erase_line(first),
+            'G' => self.col = (first.max(1) - 1).min(MAX_CURSOR_COLUMN),
+            'C' => {
+                self.col = self
+                    .col
+                    .saturating_add(first.max(1))
+                    .min(MAX_CURSOR_COLUMN.max(self.col))
+            }
+            'D' => self.col = self.col.saturating_sub(first.max(1)),
+            _ => {}
+        }
+    }
+}
+
+fn parse_extended_color(params: &[usize]) -> (Option<Color>, usize) {
+    match params.first() {
+        Some(5) => match params.get(1) {
+            Some(index) => (Some(Color::Indexed((*index).min(255) as u8)), 2),
+            None => (None, 1),
+        },
+        Some(2) if params.len() >= 4 => (
+            Some(Color::Rgb(
+                params[1].min(255) as u8,
+                params[2].min(255) as u8,
+                params[3].min(255) as u8,
+            )),
+            4,
+        ),
+        _ => (None, params.len()),
+    }
+}
+
+fn replay(bytes: &[u8]) -> Vec<Vec<Cell>> {
+    let text = String::from_utf8_lossy(bytes);
+    let mut chars = text.chars().peekable();
+    let mut screen = Screen::default();
+
+    while let Some(ch) = chars.next() {
+        match ch {
+            '\x1b' => match chars.next() {
+                Some('[') => {
+                    let mut raw = String::new();
+                    let mut action = None;
+                    for next in chars.by_ref() {
+                        if ('\x40'..='\x7e').contains(&next) {
+                            action = Some(next);
+                            break;
+                        }
+                        raw.push(next);
+                    }
+                    // Private sequences (`?25l`, `>c`, ...) never affect text.
+                    if raw.starts_with(['?', '>', '<', '=']) {
+                        continue;
+                    }
+                    let params: Vec<usize> = raw
+                        .split([';', ':'])
+                        .filter(|part| !part.is_empty())
+                        .map(|part| part.parse().unwrap_or(0))
+                        .collect();
+                    if let Some(action) = action {
+                        screen.apply_csi(&params, action);
+                    }
+                }
+                Some(']') | Some('P') | Some('_') | Some('^') => {
+                    while let Some(next) = chars.next() {
+                        if next == '\x07' {
+                            break;
+                        }
+                        if next == '\x1b' && chars.peek() == Some(&'\\') {
+                            chars.next();
+                            break;
+                        }
+                    }
+                }
+                Some('(') | Some(')') | Some('#') => {
+                    chars.next();
+                }
+                _ => {}
+            },
+            '\n' => screen.newline(),
+            '\r' => screen.col = 0,
+            '\x08' => screen.col = screen.col.saturating_sub(1),
+            '\t' => {
+                let next_stop = (screen.col / 8 + 1) * 8;
+                while screen.col < next_stop {
+                    screen.put(' ');
+                }
+            }
+            ch if ch.is_control() => {}
+            ch => screen.put(ch),
+        }
+    }
+
+    let mut lines = screen.lines;
+    for line in lines.iter_mut() {
+        while line.last().map(|cell| cell.ch == ' ') == Some(true) {
+            line.pop();
+        }
+    }
+    while lines.last().map(|line| line.is_empty()) == Some(true) {
+        lines.pop();
+    }
+    lines
+}
+
+pub fn render_text(bytes: &[u8]) -> String {
+    let lines = replay(bytes);
+    let mut output = String::new();
+    for line in lines {
+        output.extend(line.iter().map(|cell| cell.ch));
+        output.push('\n');
+    }
+    output
+}
+
+pub fn render_markdown(bytes: &[u8], title: &str) -> String {
+    let text = render_text(bytes);
+    let mut longest_run = 0;
+    let mut run = 0;
+    for ch in text.chars() {
+        if ch == '`' {
+            run += 1;
+            longest_run = longest_run.max(run);
+        } else {
+            run = 0;
+        }
+    }
+    let fence = "`".repeat((longest_run + 1).max(3));
+    format!("## {}\n\n{}text\n{}{}\n", title, fence, text, fence)
+}
+
+const ANSI_PALETTE: [(u8, u8, u8); 16] = [
+    (0x00, 0x00, 0x00),
+    (0xcd, 0x31, 0x31),
+    (0x0d, 0xbc, 0x79),
+    (0xe5, 0xe5, 0x10),
+    (0x24, 0x72, 0xc8),
+    (0xbc, 0x3f, 0xbc),
+    (0x11, 0xa8, 0xcd),
+    (0xe5, 0xe5, 0xe5),
+    (0x66, 0x66, 0x66),
+    (0xf1, 0x4c, 0x4c),
+    (0x23, 0xd1, 0x8b),
+    (0xf5, 0xf5, 0x43),
+    (0x3b, 0x8e, 0xea),
+    (0xd6, 0x70, 0xd6),
+    (0x29, 0xb8, 0xdb),
+    (0xff, 0xff, 0xff),
+];
+const DEFAULT_FOREGROUND: &str = "#d4d4d4";
+const DEFAULT_BACKGROUND: &str = "#1e1e1e";
+
+fn color_css(color: Color) -> String {
+    let (r, g, b) = match color {
+        Color::Rgb(r, g, b) => (r, g, b),
+        Color::Indexed(index) if index < 16 => ANSI_PALETTE[index as usize],
+        Color::Indexed(index) if index < 232 => {
+            let level = |value: u8| if value == 0 { 0 } else { 55 + value * 40 };
+            let cube = index - 16;
+            (level(cube / 36), level((cube / 6) % 6), level(cube % 6))
+        }
+        Color::Indexed(index) => {
+            let gray = 8 + (index - 232) * 10;
+            (gray, gray, gray)
+        }
+    };
+    format!("#{:02x}{:02x}{:02x}", r, g, b)
+}
+
+fn style_css(style: &Style) -> String {
+    let (fg, bg) = if style.inverse {
+        (
+            Some(
+                style
+                    .bg
+                    .map(color_css)
+                    .unwrap_or(DEFAULT_BACKGROUND.to_string()),
+            ),
+            Some(
+                style
+                    .fg
+                    .map(color_css)
+                    .unwrap_or(DEFAULT_FOREGROUND.to_string()),
+            ),
+        )
+    } else {
+        (style.fg.map(color_css), style.bg.map(color_css))
+    };
+    let mut rules = Vec::new();
+    if let Some(fg) = fg {
+        rules.push(format!("color:{}", fg));
+    }
+    if let Some(bg) = bg {
+        rules.push(format!("background:{}", bg));
+    }
+    if style.bold {
+        rules.push("font-weight:bold".to_string());
+    }
+    if style.dim {
+        rules.push("opacity:0.7".to_string());
+    }
+    if style.italic {
+        rules.push("font-style:italic".to_string());
+    }
+    if style.underline {
+        rules.push("text-decoration:underline".to_string());
+    }
+    rules.join(";")
+}
+
+fn escape_html(ch: char, output: &mut String) {
+    match ch {
+        '&' => output.push_str("&amp;"),
+        '<' => output.push_str("&lt;"),
+        '>' => output.push_str("&gt;"),
+        '"' => output.push_str("&quot;"),
+        ch => output.push(ch),
+    }
+}
+
+pub fn render_html(bytes: &[u8], title: &str) -> String {
+    let lines = replay(bytes);
+    let mut body = String::new();
+    for line in lines {
+        let mut index = 0;
+        while index < line.len() {
+            let style = line[index].style;
+            let mut end = index;
+            while end < line.len() && line[end].style == style {
+                end += 1;
+            }
+            let css = style_css(&style);
+            if !css.is_empty() {
+                body.push_str(&format!("<span style=\"{}\">", css));
+            }
+            for cell in &line[index..end] {
+                escape_html(cell.ch, &mut body);
+            }
+            if !css.is_empty() {
+                body.push_str("</span>");
+            }
+            index = end;
+        }
+        body.push('\n');
+    }
+
+    let mut escaped_title = String::new();
+    for ch in title.chars() {
+        escape_html(ch, &mut escaped_title);
+    }
+    format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{title}</title>\n<style>\nbody {{ margin: 0; background: {bg}; color: {fg}; }}\npre {{ margin: 0; padding: 16px; font-family: Menlo, Consolas, monospace; font-size: 13px; line-height: 1.4; white-space: pre-wrap; }}\n</style>\n</head>\n<body>\n<pre>{body}</pre>\n</body>\n</html>\n",
+        title = escaped_title,
+        bg = DEFAULT_BACKGROUND,
+        fg = DEFAULT_FOREGROUND,
+        body = body,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text(output: &str) -> String {
+        render_text(output.as_bytes())
+    }
+
+    #[test]
+    fn carriage_returns_keep_the_final_redraw() {
+        assert_eq!(text("10%\r50%\r100%\ndone\n"), "100%\ndone\n");
+        assert_eq!(text("hello\rjy\n"), "jyllo\n");
+        assert_eq!(text("ab\x08c\n"), "ac\n");
+    }
+
+    #[test]
+    fn cursor_moves_position_later_output() {
+        assert_eq!(text("abc\x1b[2Dx\n"), "axc\n");
+        assert_eq!(text("a\x1b[3Cb\n"), "a   b\n");
+        assert_eq!(text("\x1b[5Gx\n"), "    x\n");
+        assert_eq!(text("a\tb\n"), "a       b\n");
+        assert_eq!(text("\x1b[D\x1b[9Dx\n"), "x\n");
+    }
+
+    #[test]
+    fn clamps_cursor_moves_to_the_column_limit() {
+        let rendered = text("x\x1b[100000000Cy\x1b[100000000Gz\n");
+        assert_eq!(rendered.trim_end().chars().count(), MAX_CURSOR_COLUMN + 1);
+        // Both moves land on the last column, so `z` overwrites `y`.
+        assert!(rendered.trim_end().ends_with('z'));
+    }
+
+    #[test]
+    fn erase_sequences_clear_parts_of_the_line() {
+        assert_eq!(text("hello\x1b[3D\x1b[K\n"), "he\n");
+        assert_eq!(text("hello\x1b[3D\x1b[0K!\n"), "he!\n");
+        assert_eq!(text("hello\x1b[2D\x1b[1K\n"), "    o\n");
+        assert_eq!(text("hello\x1b[2K\rbye\n"), "bye\n");
+    }
+
+    #[test]
+    fn drops_styles_titles_and_private_sequences_from_text() {
+        assert_eq!(
+            text("\x1b[?25l\x1b[1;31mred\x1b[0m \x1b]0;title\x07done\x1b[?25h\n"),
+            "red done\n"
+        );
+        assert_eq!(
+            text("\x1b]8;;https://x\x1b\\link\x1b]8;;\x1b\\\n"),
+            "link\n"
+        );
+    }
+
+    #[test]
+    fn trims_trailing_blanks_and_survives_truncated_sequences() {
+        assert_eq!(text("a  \n\n\n"), "a\n");
+        assert_eq!(text("abc\x1b["), "abc\n");
+        assert_eq!(text("abc\x1b]0;unterminated"), "abc\n");
+        assert_eq!(text(""), "");
+    }
+
+    #[test]
+    fn renders_styles_and_escapes_html() {
+        let html = render_html(b"\x1b[1;31mred\x1b[0m <b>&\n", "a < b");
+        assert!(html.contains("<span style=\"color:#cd3131;font-weight:bold\">red</span>"));
+        assert!(html.contains(" &lt;b&gt;&amp;\n"));
+        assert!(html.contains("<title>a &lt; b</title>"));
+    }
+
+    #[test]
+    fn fences_markdown_longer_than_any_backtick_run() {
+        let markdown = render_markdown(b"run ````cmd````\n", "t");
+        assert!(markdown.starts_with("## t\n\n`````text\n"));
+        assert!(markdown.ends_with("\n`````\n"));
+    }
+}